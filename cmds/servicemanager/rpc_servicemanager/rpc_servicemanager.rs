@@ -15,12 +15,15 @@
 //! Implementation of the AIDL interface `IServiceManager`.
 
 use anyhow::Result;
-use binder::{Accessor, BinderFeatures, Interface, Strong};
-use libc::{sa_family_t, sockaddr_vm, AF_VSOCK};
+use binder::{Accessor, BinderFeatures, DeathRecipient, Interface, SpIBinder, Status, Strong};
+use libc::{sa_family_t, sockaddr_un, sockaddr_vm, AF_UNIX, AF_VSOCK};
 use log::{error, info};
 use rpc_servicemanager_aidl::aidl::android::os::IRpcProvider::{
     IRpcProvider, ServiceConnectionInfo::ServiceConnectionInfo,
 };
+use rpc_servicemanager_aidl::aidl::android::os::IRpcProviderCallback::{
+    BnRpcProviderCallback, IRpcProviderCallback,
+};
 use rpcbinder::{FileDescriptorTransportMode, RpcServer};
 use rustutils::sockets::android_get_control_socket;
 use servicemanager_aidl::aidl::android::os::IServiceManager::{
@@ -30,16 +33,93 @@ use servicemanager_aidl::aidl::android::os::{
     ConnectionInfo::ConnectionInfo, IClientCallback::IClientCallback,
     IServiceCallback::IServiceCallback, Service::Service, ServiceDebugInfo::ServiceDebugInfo,
 };
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 use vsock::VMADDR_CID_HOST;
 
 // Name of the socket that libbinder is expecting IServiceManager to be served from
 const RPC_SERVICEMANAGER_UDS_NAME: &str = "rpc_servicemanager";
 
+/// The service- and client-availability callbacks registered for one instance name.
+#[derive(Default)]
+struct NotificationEntry {
+    service_callbacks: BTreeMap<SpIBinder, (Strong<dyn IServiceCallback>, DeathRecipient)>,
+    client_callbacks: BTreeMap<SpIBinder, (SpIBinder, Strong<dyn IClientCallback>, DeathRecipient)>,
+}
+
+type NotificationRegistry = Arc<Mutex<HashMap<String, NotificationEntry>>>;
+
+/// Services most recently reported available by `IRpcProvider`, keyed by instance name. Consulted
+/// by `registerForNotifications` so a callback registered after the service already came up still
+/// gets an immediate `onRegistration`, matching the in-process service manager's behavior.
+type AvailableServices = Arc<Mutex<HashMap<String, SpIBinder>>>;
+
 /// Implementation of `IServiceManager`.
 pub struct RpcServiceManager {
     provider_service: Strong<dyn IRpcProvider>,
+    notifications: NotificationRegistry,
+    available_services: AvailableServices,
+}
+
+/// Receives availability/teardown events from `IRpcProvider` and fans them out to whichever
+/// `IServiceCallback`/`IClientCallback` are currently registered for that instance name.
+struct RpcProviderNotificationCallback {
+    notifications: NotificationRegistry,
+    available_services: AvailableServices,
 }
 
+impl IRpcProviderCallback for RpcProviderNotificationCallback {
+    fn onServiceAvailable(&self, name: &str, service: &binder::SpIBinder) -> binder::Result<()> {
+        self.available_services.lock().unwrap().insert(name.to_string(), service.clone());
+        // Snapshot the callbacks and drop the lock before invoking them, so a callback that
+        // re-enters `registerForNotifications`/`unregisterForNotifications` doesn't deadlock on
+        // `notifications`, and one slow callback can't block fan-out to the others.
+        let callbacks: Vec<_> = {
+            let notifications = self.notifications.lock().unwrap();
+            notifications
+                .get(name)
+                .map(|entry| entry.service_callbacks.values().map(|(cb, _)| cb.clone()).collect())
+                .unwrap_or_default()
+        };
+        for callback in callbacks {
+            callback.onRegistration(name, service).unwrap_or_else(|e| {
+                error!("Failed to notify service callback for '{name}': {e:?}");
+            });
+        }
+        Ok(())
+    }
+
+    fn onServiceUnavailable(&self, name: &str) -> binder::Result<()> {
+        self.available_services.lock().unwrap().remove(name);
+        // Client (strong-ref) callbacks use the same "went away" signal as a death
+        // notification on the service binder would, so notify them here too. See
+        // `onServiceAvailable` for why the lock is dropped before invoking callbacks.
+        let callbacks: Vec<_> = {
+            let notifications = self.notifications.lock().unwrap();
+            notifications
+                .get(name)
+                .map(|entry| {
+                    entry
+                        .client_callbacks
+                        .values()
+                        .map(|(service, cb, _)| (service.clone(), cb.clone()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        for (service, callback) in callbacks {
+            callback.onClients(&service, false).unwrap_or_else(|e| {
+                error!("Failed to notify client callback for '{name}': {e:?}");
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Interface for RpcProviderNotificationCallback {}
+
 impl IServiceManager for RpcServiceManager {
     fn getService(&self, _name: &str) -> binder::Result<Option<binder::SpIBinder>> {
         Err(binder::ExceptionCode::UNSUPPORTED_OPERATION.into())
@@ -51,15 +131,27 @@ impl IServiceManager for RpcServiceManager {
             let connection_info = provider_service.getServiceConnectionInfo(inst).unwrap();
             match connection_info {
                 ServiceConnectionInfo::Vsock(info) => {
+                    // A CID of 0 means the provider didn't specify one; the service is then
+                    // assumed to live on the host, as before.
+                    let svm_cid = if info.cid != 0 { info.cid as u32 } else { VMADDR_CID_HOST };
                     let addr = sockaddr_vm {
                         svm_family: AF_VSOCK as sa_family_t,
                         svm_reserved1: 0,
                         svm_port: info.port as u32,
-                        svm_cid: VMADDR_CID_HOST,
+                        svm_cid,
                         svm_zero: [0u8; 4],
                     };
                     Some(binder::ConnectionInfo::Vsock(addr))
                 }
+                ServiceConnectionInfo::UnixDomain(info) => {
+                    match unix_domain_sockaddr(&info.path) {
+                        Ok(addr) => Some(binder::ConnectionInfo::UnixDomain(addr)),
+                        Err(e) => {
+                            error!("Invalid UnixDomain path {}: {e}", info.path);
+                            None
+                        }
+                    }
+                }
                 #[allow(unreachable_patterns)]
                 _ => {
                     error!("Unexpected ServiceConnectionInfo type!");
@@ -92,17 +184,62 @@ impl IServiceManager for RpcServiceManager {
     }
     fn registerForNotifications(
         &self,
-        _name: &str,
-        _callback: &binder::Strong<dyn IServiceCallback>,
+        name: &str,
+        callback: &binder::Strong<dyn IServiceCallback>,
     ) -> binder::Result<()> {
-        Err(binder::ExceptionCode::UNSUPPORTED_OPERATION.into())
+        let binder = callback.as_binder();
+        let weak_notifications = Arc::downgrade(&self.notifications);
+        let name_for_death = name.to_string();
+        let binder_for_death = binder.clone();
+        let mut death_recipient = DeathRecipient::new(move || {
+            if let Some(notifications) = weak_notifications.upgrade() {
+                if let Some(entry) = notifications.lock().unwrap().get_mut(&name_for_death) {
+                    entry.service_callbacks.remove(&binder_for_death);
+                }
+            }
+        });
+        if let Err(e) = binder.clone().link_to_death(&mut death_recipient) {
+            error!("Failed to link to death for service callback on '{name}': {e:?}");
+        }
+        self.notifications
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .service_callbacks
+            .insert(binder, (callback.clone(), death_recipient));
+        // If the service is already available, deliver the initial `onRegistration` immediately,
+        // matching the in-process service manager instead of leaving the caller to wait for the
+        // next availability transition.
+        if let Some(service) = self.available_services.lock().unwrap().get(name).cloned() {
+            callback.onRegistration(name, &service).unwrap_or_else(|e| {
+                error!("Failed to notify service callback for '{name}': {e:?}");
+            });
+        }
+        Ok(())
     }
     fn unregisterForNotifications(
         &self,
-        _name: &str,
-        _callback: &binder::Strong<dyn IServiceCallback>,
+        name: &str,
+        callback: &binder::Strong<dyn IServiceCallback>,
     ) -> binder::Result<()> {
-        Err(binder::ExceptionCode::UNSUPPORTED_OPERATION.into())
+        let mut notifications = self.notifications.lock().unwrap();
+        let Some(entry) = notifications.get_mut(name) else {
+            return Err(Status::new_exception_str(
+                binder::ExceptionCode::ILLEGAL_STATE,
+                Some(format!("No notifications registered for '{name}'")),
+            ));
+        };
+        if entry.service_callbacks.remove(&callback.as_binder()).is_none() {
+            return Err(Status::new_exception_str(
+                binder::ExceptionCode::ILLEGAL_STATE,
+                Some(format!("Callback not registered for '{name}'")),
+            ));
+        }
+        if entry.service_callbacks.is_empty() && entry.client_callbacks.is_empty() {
+            notifications.remove(name);
+        }
+        Ok(())
     }
     fn isDeclared(&self, _name: &str) -> binder::Result<bool> {
         Err(binder::ExceptionCode::UNSUPPORTED_OPERATION.into())
@@ -121,18 +258,50 @@ impl IServiceManager for RpcServiceManager {
     }
     fn registerClientCallback(
         &self,
-        _name: &str,
-        _service: &binder::SpIBinder,
-        _callback: &binder::Strong<dyn IClientCallback>,
+        name: &str,
+        service: &binder::SpIBinder,
+        callback: &binder::Strong<dyn IClientCallback>,
     ) -> binder::Result<()> {
-        Err(binder::ExceptionCode::UNSUPPORTED_OPERATION.into())
+        let binder = callback.as_binder();
+        let weak_notifications = Arc::downgrade(&self.notifications);
+        let name_for_death = name.to_string();
+        let binder_for_death = binder.clone();
+        let mut death_recipient = DeathRecipient::new(move || {
+            if let Some(notifications) = weak_notifications.upgrade() {
+                if let Some(entry) = notifications.lock().unwrap().get_mut(&name_for_death) {
+                    entry.client_callbacks.remove(&binder_for_death);
+                }
+            }
+        });
+        if let Err(e) = binder.clone().link_to_death(&mut death_recipient) {
+            error!("Failed to link to death for client callback on '{name}': {e:?}");
+        }
+        self.notifications
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .client_callbacks
+            .insert(binder, (service.clone(), callback.clone(), death_recipient));
+        Ok(())
     }
     fn tryUnregisterService(
         &self,
-        _name: &str,
-        _service: &binder::SpIBinder,
+        name: &str,
+        service: &binder::SpIBinder,
     ) -> binder::Result<()> {
-        Err(binder::ExceptionCode::UNSUPPORTED_OPERATION.into())
+        let mut notifications = self.notifications.lock().unwrap();
+        let Some(entry) = notifications.get_mut(name) else {
+            return Err(Status::new_exception_str(
+                binder::ExceptionCode::ILLEGAL_STATE,
+                Some(format!("No registrations for '{name}'")),
+            ));
+        };
+        entry.client_callbacks.retain(|_, (watched, _, _)| watched != service);
+        if entry.service_callbacks.is_empty() && entry.client_callbacks.is_empty() {
+            notifications.remove(name);
+        }
+        Ok(())
     }
     fn getServiceDebugInfo(&self) -> binder::Result<Vec<ServiceDebugInfo>> {
         Err(binder::ExceptionCode::UNSUPPORTED_OPERATION.into())
@@ -144,10 +313,80 @@ impl IServiceManager for RpcServiceManager {
 
 impl Interface for RpcServiceManager {}
 
+// Builds a `sockaddr_un` for a filesystem-backed Unix domain socket path.
+//
+// The FD-mode RPC server connects to the socket by opening the path directly, so abstract
+// paths (those beginning with a NUL byte) are rejected rather than silently truncated.
+fn unix_domain_sockaddr(path: &str) -> Result<sockaddr_un, String> {
+    if path.starts_with('\0') {
+        return Err("abstract socket paths are not supported".to_string());
+    }
+    let path_bytes = path.as_bytes();
+    // Reserve one byte for the NUL terminator.
+    let mut sun = unsafe { mem::zeroed::<sockaddr_un>() };
+    if path_bytes.len() >= sun.sun_path.len() {
+        return Err(format!(
+            "path is {} bytes, which does not fit in sun_path ({} bytes)",
+            path_bytes.len(),
+            sun.sun_path.len() - 1
+        ));
+    }
+    sun.sun_family = AF_UNIX as sa_family_t;
+    for (dst, &src) in sun.sun_path.iter_mut().zip(path_bytes.iter()) {
+        *dst = src as libc::c_char;
+    }
+    Ok(sun)
+}
+
 impl RpcServiceManager {
     /// Creates a new `RpcServiceManager` instance from the `IServiceManager` reference.
+    ///
+    /// Registers a single `IRpcProviderCallback` with the provider so that availability and
+    /// teardown events can be fanned out to whichever clients have since registered for
+    /// notifications on the affected instance name.
     fn new(provider_service: Strong<dyn IRpcProvider>) -> RpcServiceManager {
-        Self { provider_service }
+        let notifications: NotificationRegistry = Arc::new(Mutex::new(Default::default()));
+        let available_services: AvailableServices = Arc::new(Mutex::new(HashMap::new()));
+        let provider_callback = BnRpcProviderCallback::new_binder(
+            RpcProviderNotificationCallback {
+                notifications: notifications.clone(),
+                available_services: available_services.clone(),
+            },
+            BinderFeatures::default(),
+        );
+        if let Err(e) = provider_service.registerNotificationCallback(&provider_callback) {
+            error!("Failed to register notification callback with IRpcProvider: {e:?}");
+        }
+        Self { provider_service, notifications, available_services }
+    }
+}
+
+/// An owned handle to a running `RpcServiceManager`.
+///
+/// The server runs on a background thread for as long as this handle is alive. Dropping it (or
+/// calling [`RpcServiceManagerHandle::shutdown`] explicitly) stops the server and joins the
+/// worker thread, so callers can cleanly tear the service down instead of leaking a
+/// join-forever thread.
+pub struct RpcServiceManagerHandle {
+    server: Arc<RpcServer>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl RpcServiceManagerHandle {
+    /// Shuts the server down and waits for its worker thread to exit.
+    ///
+    /// Safe to call more than once; subsequent calls are no-ops.
+    pub fn shutdown(&mut self) {
+        self.server.shutdown();
+        if let Some(join_handle) = self.join_handle.take() {
+            let _ = join_handle.join();
+        }
+    }
+}
+
+impl Drop for RpcServiceManagerHandle {
+    fn drop(&mut self) {
+        self.shutdown();
     }
 }
 
@@ -156,15 +395,20 @@ impl RpcServiceManager {
 /// servicemanager_fd is an optional argument to provide the Unix Domain Socked file
 /// descriptor to use for the server. If None is provided, then it will use the default
 /// of RPC_SERVICEMANAGER_UDS_NAME to get the FD.
-pub fn register_rpc_servicemanager(provider_service: Strong<dyn IRpcProvider>) -> Result<()> {
+///
+/// Returns a [`RpcServiceManagerHandle`] that owns the server's lifetime; dropping it (or
+/// calling `shutdown()` on it) stops the server and joins its worker thread.
+pub fn register_rpc_servicemanager(
+    provider_service: Strong<dyn IRpcProvider>,
+) -> Result<RpcServiceManagerHandle> {
     let rpc_servicemanager_binder = BnServiceManager::new_binder(
         RpcServiceManager::new(provider_service),
         BinderFeatures::default(),
     );
-    let server = RpcServer::new_bound_socket(
+    let server = Arc::new(RpcServer::new_bound_socket(
         rpc_servicemanager_binder.as_binder(),
         android_get_control_socket(RPC_SERVICEMANAGER_UDS_NAME)?,
-    )?;
+    )?);
     // Required for the FD being passed through libbinder's accessor binder
     server.set_supported_file_descriptor_transport_modes(&[FileDescriptorTransportMode::Unix]);
 
@@ -173,9 +417,10 @@ pub fn register_rpc_servicemanager(provider_service: Strong<dyn IRpcProvider>) -
         error!("failed to set ro.servicemanager.ready {:?}", e);
     }
 
-    // Move server reference into a background thread and run it forever.
-    std::thread::spawn(move || {
-        server.join();
+    // Move server reference into a background thread and run it until shut down.
+    let server_for_thread = server.clone();
+    let join_handle = std::thread::spawn(move || {
+        server_for_thread.join();
     });
-    Ok(())
+    Ok(RpcServiceManagerHandle { server, join_handle: Some(join_handle) })
 }
@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use crate::FileDescriptorTransportMode;
 use alloc::boxed::Box;
 use binder::{unstable_api::AsNative, SpIBinder};
 use libc::size_t;
@@ -37,8 +38,15 @@ impl<T> PerSessionCallback for T where
 {
 }
 
+/// Trait alias for an authorization policy consulted for every new connection, before the
+/// per-session callback is invoked. Returning `false` rejects the peer without allocating an
+/// `AIBinder` for it.
+pub trait ConnectionPolicy: Fn(&ClientIdentifier) -> bool + Send + Sync + 'static {}
+impl<T> ConnectionPolicy for T where T: Fn(&ClientIdentifier) -> bool + Send + Sync + 'static {}
+
 pub struct RpcServer {
     inner: *mut binder_rpc_server_bindgen::ARpcServerTrusty,
+    policy: Option<Box<dyn ConnectionPolicy>>,
 }
 
 /// SAFETY: The opaque handle points to a heap allocation
@@ -69,6 +77,18 @@ impl RpcServer {
     /// for every new connection. The closure gets the `ClientIdentifier` of
     /// the peer and can accept or reject that connection.
     pub fn new_per_session<F: PerSessionCallback>(f: F) -> RpcServer {
+        Self::new_per_session_with_policy(f, None::<fn(&ClientIdentifier) -> bool>)
+    }
+
+    /// Allocates a new per-session RpcServer object with a connection authorization policy.
+    ///
+    /// `policy`, if given, is consulted for every new connection before the per-session
+    /// callback runs. A peer rejected by the policy is closed immediately, without invoking
+    /// `ARpcServerTrusty_handleConnect` or the per-session callback.
+    pub fn new_per_session_with_policy<F: PerSessionCallback, P: ConnectionPolicy>(
+        f: F,
+        policy: Option<P>,
+    ) -> RpcServer {
         // SAFETY: Takes ownership of the returned handle, which has correct refcount.
         let inner = unsafe {
             binder_rpc_server_bindgen::ARpcServerTrusty_newPerSession(
@@ -77,7 +97,40 @@ impl RpcServer {
                 Some(per_session_callback_deleter::<F>),
             )
         };
-        RpcServer { inner }
+        RpcServer { inner, policy: policy.map(|p| Box::new(p) as Box<dyn ConnectionPolicy>) }
+    }
+
+    /// Returns `true` if the connection should be accepted, consulting the configured
+    /// authorization policy (if any).
+    fn is_authorized(&self, client_identifier: &ClientIdentifier) -> bool {
+        self.policy.as_ref().map_or(true, |policy| policy(client_identifier))
+    }
+
+    /// Sets the file descriptor transport modes supported by this server, so that FD-carrying
+    /// accessor binders negotiated over this session can be passed to clients.
+    pub fn set_supported_file_descriptor_transport_modes(
+        &self,
+        modes: &[FileDescriptorTransportMode],
+    ) {
+        let modes: Vec<u8> = modes.iter().map(|mode| *mode as u8).collect();
+        // SAFETY: `self.inner` is a valid, non-null pointer for the lifetime of `self`, and the
+        // modes slice is only borrowed for the duration of the call.
+        unsafe {
+            binder_rpc_server_bindgen::ARpcServerTrusty_setSupportedFileDescriptorTransportModes(
+                self.inner,
+                modes.as_ptr(),
+                modes.len(),
+            );
+        }
+    }
+
+    /// Sets the maximum number of concurrent threads/connections this server will service, so
+    /// per-session servers can bound their resource usage.
+    pub fn set_max_threads(&self, max_threads: usize) {
+        // SAFETY: `self.inner` is a valid, non-null pointer for the lifetime of `self`.
+        unsafe {
+            binder_rpc_server_bindgen::ARpcServerTrusty_setMaxThreads(self.inner, max_threads);
+        }
     }
 }
 
@@ -152,8 +205,11 @@ impl UnbufferedService for RpcServer {
         handle: &Handle,
         peer: &Uuid,
     ) -> tipc::Result<ConnectResult<Self::Connection>> {
-        let mut conn = RpcServerConnection { ctx: std::ptr::null_mut() };
         let client_identifier = ClientIdentifier::UUID(peer.clone());
+        if !self.is_authorized(&client_identifier) {
+            return Ok(ConnectResult::CloseConnection);
+        }
+        let mut conn = RpcServerConnection { ctx: std::ptr::null_mut() };
         let mut data = client_identifier.as_tagged_bytes();
         let len = data.len();
         // SAFETY: This unsafe block calls into a C++ function, which is considered safe, i.e. it
@@ -202,6 +258,9 @@ impl UnbufferedService for RpcServer {
         handle: &Handle,
         client_identifier: &ClientIdentifier,
     ) -> tipc::Result<ConnectResult<Self::Connection>> {
+        if !self.is_authorized(client_identifier) {
+            return Ok(ConnectResult::CloseConnection);
+        }
         let mut conn = RpcServerConnection { ctx: std::ptr::null_mut() };
         let mut data = client_identifier.as_tagged_bytes();
         let len = data.len();
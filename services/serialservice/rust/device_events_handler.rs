@@ -17,13 +17,50 @@
 use android_hardware_serialservice::aidl::android::hardware::serialservice::SerialPortInfo::SerialPortInfo;
 use anyhow::Result;
 use futures::stream::BoxStream;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::task::JoinHandle;
 use tokio_stream::StreamExt;
-use ueventd::device::{Device, FilesystemAttributeMap};
+use ueventd::device::Device;
 use ueventd::event::{DeviceEvent, DeviceType, EventType};
 
-use crate::driver_type_finder::DriverTypeFinder;
+use crate::driver_type_finder::{refine_driver_type, DriverTypeFinder};
+use crate::serial_port_policy::SerialPortPolicy;
+use crate::sysfs_attr::{read_hex_attr, read_string_attr};
+
+/// Sysfs directory enumerated by `scan_existing` to find TTY devices already present at
+/// startup.
+#[cfg(not(test))]
+const TTY_CLASS_DIR: &str = "/sys/class/tty";
+/// Unit tests drive the handler through the event stream alone, so the cold-boot scan reads a
+/// directory that does not exist and finds nothing on the host running them.
+#[cfg(test)]
+const TTY_CLASS_DIR: &str = "/nonexistent/sys/class/tty";
+
+/// Root of the sysfs hierarchy, used to resolve a `Device` from a `/sys/class/tty/<name>` path.
+const SYSFS_ROOT: &str = "/sys";
+
+/// Sysfs directory listing devices on the `serial-base` bus, also walked by `scan_existing`: a
+/// UART's tty child is not guaranteed to have materialized under `TTY_CLASS_DIR` by the instant
+/// the cold-boot scan runs.
+#[cfg(not(test))]
+const SERIAL_BASE_BUS_DIR: &str = "/sys/bus/serial-base/devices";
+/// See the test-only `TTY_CLASS_DIR`.
+#[cfg(test)]
+const SERIAL_BASE_BUS_DIR: &str = "/nonexistent/sys/bus/serial-base/devices";
+
+/// Initial delay before the first reconnect attempt after the event stream terminates.
+const INITIAL_RECONNECT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Upper bound on the reconnect delay, reached after repeated consecutive failures.
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Builds a fresh event stream, invoked whenever the previous one terminates so the handler can
+/// resubscribe to ueventd.
+pub type StreamFactory = Box<dyn Fn() -> BoxStream<'static, DeviceEvent> + Send>;
 
 #[mockall::automock]
 pub trait DeviceEventCallback {
@@ -34,28 +71,134 @@ pub trait DeviceEventCallback {
 /// Handles the stream of /dev events coming from ueventd Watcher.
 pub struct DeviceEventsHandler {
     stream: BoxStream<'static, DeviceEvent>,
+    stream_factory: StreamFactory,
     callback: Box<dyn DeviceEventCallback + Send>,
     driver_type_finder: Arc<Mutex<dyn DriverTypeFinder + Send>>,
+    policy: Arc<dyn SerialPortPolicy + Send + Sync>,
+    /// Names of devices reported through `on_device_added`, so a device found by the cold-boot
+    /// `scan_existing` pass isn't reported a second time when its live Add event arrives from the
+    /// stream during startup, and so `on_device_removed` is only fired for devices that were
+    /// actually reported (i.e. not filtered out by `policy`).
+    known_devices: HashSet<String>,
 }
 
 struct UsbDeviceId {
     vendor_id: i32,
     product_id: i32,
+    serial_number: String,
+    manufacturer: String,
+    product: String,
 }
 
 impl DeviceEventsHandler {
     pub async fn start_new(
-        stream: BoxStream<'static, DeviceEvent>,
+        stream_factory: StreamFactory,
         callback: Box<dyn DeviceEventCallback + Send>,
         driver_type_finder: Arc<Mutex<dyn DriverTypeFinder + Send>>,
+        policy: Arc<dyn SerialPortPolicy + Send + Sync>,
     ) -> JoinHandle<()> {
-        let handler = DeviceEventsHandler { stream, callback, driver_type_finder };
+        let stream = stream_factory();
+        let mut handler = DeviceEventsHandler {
+            stream,
+            stream_factory,
+            callback,
+            driver_type_finder,
+            policy,
+            known_devices: HashSet::new(),
+        };
+        handler.scan_existing().await;
         tokio::spawn(handler.run())
     }
 
+    /// Synchronously enumerates devices already present under `/sys/class/tty` and on the
+    /// `serial-base` bus at startup and feeds each one through `handle_device_event` as a
+    /// synthesized `EventType::Add`, so that ports already plugged in before `serialservice`
+    /// starts are still reported. Runs once in `start_new`, before `run` begins draining the live
+    /// stream, and again in `run` after each reconnect to the event stream.
+    /// `handle_device_event`'s own `known_devices` bookkeeping de-duplicates a device
+    /// seen through both sources, or through a live Add event arriving during startup.
+    async fn scan_existing(&mut self) {
+        self.scan_tty_class_dir().await;
+        self.scan_serial_base_bus_dir().await;
+    }
+
+    /// Walks `TTY_CLASS_DIR`, where every TTY device node normally has an entry.
+    async fn scan_tty_class_dir(&mut self) {
+        let entries = match fs::read_dir(TTY_CLASS_DIR) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("Could not read {TTY_CLASS_DIR}: {e}");
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            let syspath = Path::new(TTY_CLASS_DIR).join(&name);
+            self.scan_device_at(&name, &syspath).await;
+        }
+    }
+
+    /// Walks `SERIAL_BASE_BUS_DIR`, covering a UART's tty child in the (unusual, but possible)
+    /// case that it hasn't also surfaced under `TTY_CLASS_DIR` yet.
+    async fn scan_serial_base_bus_dir(&mut self) {
+        let entries = match fs::read_dir(SERIAL_BASE_BUS_DIR) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::debug!("Could not read {SERIAL_BASE_BUS_DIR}: {e}");
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            let Ok(tty_entries) = fs::read_dir(entry.path().join("tty")) else {
+                continue;
+            };
+            for tty_entry in tty_entries.flatten() {
+                let Some(name) = tty_entry.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                self.scan_device_at(&name, &tty_entry.path()).await;
+            }
+        }
+    }
+
+    /// Builds a `Device` rooted at `syspath` and feeds it through `handle_device_event` as a
+    /// synthesized `EventType::Add` for the device node `/dev/<name>`.
+    async fn scan_device_at(&mut self, name: &str, syspath: &Path) {
+        let device = match Device::with_root_and_syspath(Path::new(SYSFS_ROOT), syspath) {
+            Ok(device) => device,
+            Err(e) => {
+                log::debug!("Could not build Device for {}: {e}", syspath.display());
+                return;
+            }
+        };
+        self.handle_device_event(DeviceEvent {
+            event_type: EventType::Add,
+            device_type: DeviceType::DeviceNode { devnode_path: Path::new("/dev").join(name) },
+            device,
+        })
+        .await;
+    }
+
+    /// Drains the event stream, and whenever it terminates (the ueventd `Watcher` socket closes
+    /// or crashes), re-subscribes via `stream_factory` and re-runs the cold-boot scan so no
+    /// device is missed during the gap. Reconnect attempts that fail before a single event is
+    /// delivered back off exponentially, up to `MAX_RECONNECT_BACKOFF`.
     async fn run(mut self) {
-        while let Some(device_event) = self.stream.next().await {
-            self.handle_device_event(device_event).await;
+        let mut backoff = INITIAL_RECONNECT_BACKOFF;
+        loop {
+            let mut received_any = false;
+            while let Some(device_event) = self.stream.next().await {
+                received_any = true;
+                self.handle_device_event(device_event).await;
+            }
+            log::error!("ueventd event stream ended; reconnecting in {backoff:?}");
+            tokio::time::sleep(backoff).await;
+            backoff =
+                if received_any { INITIAL_RECONNECT_BACKOFF } else { (backoff * 2).min(MAX_RECONNECT_BACKOFF) };
+            self.stream = (self.stream_factory)();
+            self.scan_existing().await;
         }
     }
 
@@ -71,6 +214,15 @@ impl DeviceEventsHandler {
         let name = name.to_str().expect("Device paths should not have non-UTF-8 characters");
         match device_event.event_type {
             EventType::Add => {
+                if self.known_devices.contains(name) {
+                    // Already reported by the cold-boot scan; the live Add event for the same
+                    // device arriving during startup is a duplicate.
+                    return;
+                }
+                // A USB-serial module typically registers its driver on the very probe that
+                // creates this device node, so the cache must be dropped before `find` to see it
+                // immediately rather than only on the pre-existing lazy retry-on-miss path.
+                self.driver_type_finder.lock().unwrap().invalidate_cache();
                 let Ok(driver_type) = ({
                     let driver_type_finder = self.driver_type_finder.lock().unwrap();
                     driver_type_finder.find(&devnode_path)
@@ -82,17 +234,38 @@ impl DeviceEventsHandler {
                 // If such a dir doesn't exist, we report "virtual" subsystem
                 let subsystem_opt = device_event.device.device().and_then(|d| d.subsystem());
                 let usb_device_id = UsbDeviceId::find_for_device(&device_event.device);
+                let driver_type = refine_driver_type(
+                    driver_type,
+                    usb_device_id.vendor_id,
+                    usb_device_id.product_id,
+                );
                 let info = SerialPortInfo {
                     name: name.to_string(),
                     subsystem: subsystem_opt.unwrap_or("virtual".to_string()),
                     driverType: driver_type,
                     vendorId: usb_device_id.vendor_id,
                     productId: usb_device_id.product_id,
+                    serialNumber: usb_device_id.serial_number,
+                    manufacturer: usb_device_id.manufacturer,
+                    product: usb_device_id.product,
                 };
+                if !self.policy.is_visible(&info) {
+                    log::debug!("{} filtered out by serial port policy", name);
+                    return;
+                }
+                self.known_devices.insert(name.to_string());
                 self.callback.on_device_added(info);
             }
             EventType::Remove => {
-                self.callback.on_device_removed(name);
+                // A torn-down USB-serial driver follows the same disconnect as the device node,
+                // so drop the cache here too rather than leaving a removed driver's entry to
+                // linger until the next unrelated cache miss.
+                self.driver_type_finder.lock().unwrap().invalidate_cache();
+                // Only report the removal if the device was actually reported as added; this
+                // also suppresses removal events for devices that were filtered out by `policy`.
+                if self.known_devices.remove(name) {
+                    self.callback.on_device_removed(name);
+                }
             }
         }
     }
@@ -107,23 +280,26 @@ impl UsbDeviceId {
         let mut current_opt = device.parent_with_subsystem(subsystem);
         while let Some(current) = current_opt {
             let attrs = current.sysattrs();
-            let vendor_id = Self::read_hex_attr(&attrs, "idVendor");
+            let vendor_id = read_hex_attr(&attrs, "idVendor");
             if vendor_id.is_ok() {
-                let product_id = Self::read_hex_attr(&attrs, "idProduct");
+                let product_id = read_hex_attr(&attrs, "idProduct");
                 return Self {
                     vendor_id: vendor_id.unwrap(),
                     product_id: product_id.unwrap_or(-1),
+                    serial_number: read_string_attr(&attrs, "serial"),
+                    manufacturer: read_string_attr(&attrs, "manufacturer"),
+                    product: read_string_attr(&attrs, "product"),
                 };
             }
             current_opt = current.parent_with_subsystem(subsystem);
         }
-        Self { vendor_id: -1, product_id: -1 }
-    }
-
-    fn read_hex_attr(attrs: &FilesystemAttributeMap, name: &str) -> Result<i32> {
-        let attr_value = attrs.get(name)?;
-        let hex_value = attr_value.trim();
-        Ok(i32::from_str_radix(hex_value, 16)?)
+        Self {
+            vendor_id: -1,
+            product_id: -1,
+            serial_number: String::new(),
+            manufacturer: String::new(),
+            product: String::new(),
+        }
     }
 }
 
@@ -139,6 +315,16 @@ mod tests {
     use ueventd::mock_sysfs::{MockSysfs, SysfsFile};
 
     use crate::driver_type_finder::MockDriverTypeFinder;
+    use crate::serial_port_policy::MockSerialPortPolicy;
+    use crate::sysfs_test_fixtures::create_usb_device_in_mock_sysfs;
+
+    /// Builds a `SerialPortPolicy` that allows every device, for tests not concerned with
+    /// filtering.
+    fn allow_all_policy() -> Arc<dyn SerialPortPolicy + Send + Sync> {
+        let mut policy = MockSerialPortPolicy::new();
+        policy.expect_is_visible().returning(|_| true);
+        Arc::new(policy)
+    }
 
     fn init_test_logging() {
         android_logger::init_once(
@@ -148,18 +334,17 @@ mod tests {
         );
     }
 
-    #[tokio::test]
+    #[tokio::test(start_paused = true)]
     async fn test_handle_add_serial_device() {
         init_test_logging();
-        let (device, _sysfs_dir) = create_usb_device_in_mock_sysfs();
-        let stream = tokio_stream::iter(vec![DeviceEvent {
+        let (device, _sysfs_dir) = create_usb_device_in_mock_sysfs(true);
+        let factory = single_shot_stream_factory(vec![DeviceEvent {
             event_type: EventType::Add,
             device_type: DeviceType::DeviceNode {
                 devnode_path: Path::new("/dev/ttyACM0").to_path_buf(),
             },
             device,
-        }])
-        .boxed();
+        }]);
         let mut callback = MockDeviceEventCallback::new();
         callback.expect_on_device_added().times(1).returning(|info| {
             assert_eq!(info.name, "ttyACM0".to_string());
@@ -167,9 +352,13 @@ mod tests {
             assert_eq!(info.driverType, "serial".to_string());
             assert_eq!(info.vendorId, 0x0694);
             assert_eq!(info.productId, 0x0009);
+            assert_eq!(info.serialNumber, "ABC123".to_string());
+            assert_eq!(info.manufacturer, "Acme Corp".to_string());
+            assert_eq!(info.product, "Serial Adapter".to_string());
         });
         callback.expect_on_device_removed().never();
         let mut driver_type_finder = MockDriverTypeFinder::new();
+        driver_type_finder.expect_invalidate_cache().times(1).return_const(());
         driver_type_finder
             .expect_find()
             .with(eq(Path::new("/dev/ttyACM0")))
@@ -177,26 +366,27 @@ mod tests {
             .returning(|_| Ok("serial".to_string()));
 
         let handle = DeviceEventsHandler::start_new(
-            stream,
+            factory,
             Box::new(callback),
             Arc::new(Mutex::new(driver_type_finder)) as Arc<Mutex<dyn DriverTypeFinder + Send>>,
+            allow_all_policy(),
         )
         .await;
-        handle.await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        handle.abort();
     }
 
-    #[tokio::test]
+    #[tokio::test(start_paused = true)]
     async fn test_handle_add_device_without_usb_id() {
         init_test_logging();
         let (device, _sysfs_dir) = create_uart_device_in_mock_sysfs();
-        let stream = tokio_stream::iter(vec![DeviceEvent {
+        let factory = single_shot_stream_factory(vec![DeviceEvent {
             event_type: EventType::Add,
             device_type: DeviceType::DeviceNode {
                 devnode_path: Path::new("/dev/ttyS0").to_path_buf(),
             },
             device,
-        }])
-        .boxed();
+        }]);
         let mut callback = MockDeviceEventCallback::new();
         callback.expect_on_device_added().times(1).returning(|info| {
             assert_eq!(info.name, "ttyS0".to_string());
@@ -204,9 +394,13 @@ mod tests {
             assert_eq!(info.driverType, "serial".to_string());
             assert_eq!(info.vendorId, -1);
             assert_eq!(info.productId, -1);
+            assert_eq!(info.serialNumber, "".to_string());
+            assert_eq!(info.manufacturer, "".to_string());
+            assert_eq!(info.product, "".to_string());
         });
         callback.expect_on_device_removed().never();
         let mut driver_type_finder = MockDriverTypeFinder::new();
+        driver_type_finder.expect_invalidate_cache().times(1).return_const(());
         driver_type_finder
             .expect_find()
             .with(eq(Path::new("/dev/ttyS0")))
@@ -214,19 +408,21 @@ mod tests {
             .returning(|_| Ok("serial".to_string()));
 
         let handle = DeviceEventsHandler::start_new(
-            stream,
+            factory,
             Box::new(callback),
             Arc::new(Mutex::new(driver_type_finder)) as Arc<Mutex<dyn DriverTypeFinder + Send>>,
+            allow_all_policy(),
         )
         .await;
-        handle.await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        handle.abort();
     }
 
-    #[tokio::test]
+    #[tokio::test(start_paused = true)]
     async fn test_handle_add_and_remove_serial_device() {
         init_test_logging();
-        let (device, _sysfs_dir) = create_usb_device_in_mock_sysfs();
-        let stream = tokio_stream::iter(vec![
+        let (device, _sysfs_dir) = create_usb_device_in_mock_sysfs(true);
+        let factory = single_shot_stream_factory(vec![
             DeviceEvent {
                 event_type: EventType::Add,
                 device_type: DeviceType::DeviceNode {
@@ -241,8 +437,7 @@ mod tests {
                 },
                 device,
             },
-        ])
-        .boxed();
+        ]);
         let mut callback = MockDeviceEventCallback::new();
         callback.expect_on_device_added().times(1).returning(|info| {
             assert_eq!(info.name, "ttyACM0".to_string());
@@ -250,11 +445,15 @@ mod tests {
             assert_eq!(info.driverType, "serial".to_string());
             assert_eq!(info.vendorId, 0x0694);
             assert_eq!(info.productId, 0x0009);
+            assert_eq!(info.serialNumber, "ABC123".to_string());
+            assert_eq!(info.manufacturer, "Acme Corp".to_string());
+            assert_eq!(info.product, "Serial Adapter".to_string());
         });
         callback.expect_on_device_removed().times(1).returning(|name| {
             assert_eq!(name, "ttyACM0");
         });
         let mut driver_type_finder = MockDriverTypeFinder::new();
+        driver_type_finder.expect_invalidate_cache().times(2).return_const(());
         driver_type_finder
             .expect_find()
             .with(eq(Path::new("/dev/ttyACM0")))
@@ -262,30 +461,76 @@ mod tests {
             .returning(|_| Ok("serial".to_string()));
 
         let handle = DeviceEventsHandler::start_new(
-            stream,
+            factory,
+            Box::new(callback),
+            Arc::new(Mutex::new(driver_type_finder)) as Arc<Mutex<dyn DriverTypeFinder + Send>>,
+            allow_all_policy(),
+        )
+        .await;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        handle.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_handle_add_and_remove_device_filtered_by_policy() {
+        init_test_logging();
+        let (device, _sysfs_dir) = create_usb_device_in_mock_sysfs(true);
+        let factory = single_shot_stream_factory(vec![
+            DeviceEvent {
+                event_type: EventType::Add,
+                device_type: DeviceType::DeviceNode {
+                    devnode_path: Path::new("/dev/ttyACM0").to_path_buf(),
+                },
+                device: device.clone(),
+            },
+            DeviceEvent {
+                event_type: EventType::Remove,
+                device_type: DeviceType::DeviceNode {
+                    devnode_path: Path::new("/dev/ttyACM0").to_path_buf(),
+                },
+                device,
+            },
+        ]);
+        let mut callback = MockDeviceEventCallback::new();
+        callback.expect_on_device_added().never();
+        callback.expect_on_device_removed().never();
+        let mut driver_type_finder = MockDriverTypeFinder::new();
+        driver_type_finder.expect_invalidate_cache().times(2).return_const(());
+        driver_type_finder
+            .expect_find()
+            .with(eq(Path::new("/dev/ttyACM0")))
+            .times(1)
+            .returning(|_| Ok("serial".to_string()));
+        let mut policy = MockSerialPortPolicy::new();
+        policy.expect_is_visible().times(1).returning(|_| false);
+
+        let handle = DeviceEventsHandler::start_new(
+            factory,
             Box::new(callback),
             Arc::new(Mutex::new(driver_type_finder)) as Arc<Mutex<dyn DriverTypeFinder + Send>>,
+            Arc::new(policy),
         )
         .await;
-        handle.await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        handle.abort();
     }
 
-    #[tokio::test]
+    #[tokio::test(start_paused = true)]
     async fn test_handle_add_alien_device() {
         init_test_logging();
-        let (device, _sysfs_dir) = create_usb_device_in_mock_sysfs();
-        let stream = tokio_stream::iter(vec![DeviceEvent {
+        let (device, _sysfs_dir) = create_usb_device_in_mock_sysfs(true);
+        let factory = single_shot_stream_factory(vec![DeviceEvent {
             event_type: EventType::Add,
             device_type: DeviceType::DeviceNode {
                 devnode_path: Path::new("/dev/alien").to_path_buf(),
             },
             device,
-        }])
-        .boxed();
+        }]);
         let mut callback = MockDeviceEventCallback::new();
         callback.expect_on_device_added().never();
         callback.expect_on_device_removed().never();
         let mut driver_type_finder = MockDriverTypeFinder::new();
+        driver_type_finder.expect_invalidate_cache().times(1).return_const(());
         driver_type_finder
             .expect_find()
             .with(eq(Path::new("/dev/alien")))
@@ -293,65 +538,25 @@ mod tests {
             .returning(|_| Err(anyhow!("Driver type not found")));
 
         let handle = DeviceEventsHandler::start_new(
-            stream,
+            factory,
             Box::new(callback),
             Arc::new(Mutex::new(driver_type_finder)) as Arc<Mutex<dyn DriverTypeFinder + Send>>,
+            allow_all_policy(),
         )
         .await;
-        handle.await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        handle.abort();
     }
 
-    fn create_usb_device_in_mock_sysfs() -> (Device, MockSysfs) {
-        let sysfs = SysfsFile::Dir(HashMap::from([
-            (
-                "devices/pci0000:00/0000:00:14.0/usb3/3-8",
-                SysfsFile::Dir(HashMap::from([
-                    (
-                        "3-8:1.1",
-                        SysfsFile::Dir(HashMap::from([
-                            (
-                                "tty/ttyACM0",
-                                SysfsFile::Dir(HashMap::from([
-                                    (
-                                        "device",
-                                        SysfsFile::Dir(HashMap::from([
-                                            (
-                                                "subsystem",
-                                                SysfsFile::Symlink(
-                                                    "../../../../../../../../../bus/usb",
-                                                ),
-                                            ),
-                                            ("uevent", SysfsFile::RegularFile("")),
-                                        ])),
-                                    ),
-                                    (
-                                        "subsystem",
-                                        SysfsFile::Symlink("../../../../../../../../class/tty"),
-                                    ),
-                                    ("uevent", SysfsFile::RegularFile("")),
-                                ])),
-                            ),
-                            ("subsystem", SysfsFile::Symlink("../../../../../../bus/usb")),
-                            ("uevent", SysfsFile::RegularFile("")),
-                        ])),
-                    ),
-                    ("subsystem", SysfsFile::Symlink("../../../../../bus/usb")),
-                    ("idVendor", SysfsFile::RegularFile("0694\n")),
-                    ("idProduct", SysfsFile::RegularFile("0009\n")),
-                    ("uevent", SysfsFile::RegularFile("")),
-                ])),
-            ),
-            ("bus/usb", SysfsFile::Dir(HashMap::new())),
-            ("class/tty", SysfsFile::Dir(HashMap::new())),
-        ]));
-        let sysfs_dir = match MockSysfs::new(sysfs) {
-            Ok(ms) => ms,
-            Err(e) => panic!("Could not create mock sysfs: {}", e),
-        };
-        let sysfs_path =
-            sysfs_dir.path().join("devices/pci0000:00/0000:00:14.0/usb3/3-8/3-8:1.1/tty/ttyACM0");
-        let device = Device::with_root_and_syspath(sysfs_dir.path(), &sysfs_path).unwrap();
-        (device, sysfs_dir)
+    /// Builds a `StreamFactory` that yields `events` once and then, on every subsequent
+    /// reconnect, a stream that never resolves — so the test's single round of events is
+    /// delivered without the handler's automatic-reconnect loop spinning in the background.
+    fn single_shot_stream_factory(events: Vec<DeviceEvent>) -> StreamFactory {
+        let events = Mutex::new(Some(events));
+        Box::new(move || match events.lock().unwrap().take() {
+            Some(events) => tokio_stream::iter(events).boxed(),
+            None => futures::stream::pending().boxed(),
+        })
     }
 
     fn create_uart_device_in_mock_sysfs() -> (Device, MockSysfs) {
@@ -17,10 +17,12 @@
 use anyhow::{anyhow, bail, Result};
 use core::cell::RefCell;
 use nix::libc;
+use std::collections::HashMap;
 use std::fs;
 use std::ops::RangeInclusive;
 use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
 
 /// Contains device numbers supported by TTY drivers that allow to distinguish TTY devices
 /// among all devices in /dev.
@@ -28,11 +30,67 @@ use std::path::{Path, PathBuf};
 /// The file containing descriptions of all TTY drivers.
 const DRIVERS_FILE_PATH: &str = "/proc/tty/drivers";
 
-/// Finds type of a TTY driver corresponding to a given major and minor numbers of the device node.
+/// The driver type reported by `DriverTypeFinder::find` for any undifferentiated TTY device,
+/// e.g. a generic `ttyUSB*`/`ttyACM*` node.
+const GENERIC_SERIAL_TYPE: &str = "serial";
+
+/// Known (vendorId, productId) pairs for common USB-serial bridges, consulted by
+/// `refine_driver_type` when the path-based lookup only yields [`GENERIC_SERIAL_TYPE`].
+static USB_DRIVER_TYPES: LazyLock<HashMap<(i32, i32), &'static str>> = LazyLock::new(|| {
+    HashMap::from([
+        ((0x0403, 0x6001), "ftdi"),
+        ((0x10C4, 0xEA60), "cp210x"),
+        ((0x1A86, 0x7523), "ch340"),
+        ((0x067B, 0x2303), "pl2303"),
+    ])
+});
+
+/// Vendor-only fallback for when an exact (vendorId, productId) match isn't found, e.g. because
+/// `productId` is unknown (-1) or belongs to a variant not listed in [`USB_DRIVER_TYPES`].
+static USB_VENDOR_DRIVER_TYPES: LazyLock<HashMap<i32, &'static str>> = LazyLock::new(|| {
+    HashMap::from([(0x0403, "ftdi"), (0x10C4, "cp210x"), (0x1A86, "ch340"), (0x067B, "pl2303")])
+});
+
+/// Refines `driver_type` using the USB vendor/product ID of the device, when the path-based
+/// lookup only produced the generic `"serial"` type. An exact (vendorId, productId) hit wins
+/// over a vendor-only hit; a miss leaves `driver_type` untouched.
+pub fn refine_driver_type(driver_type: String, vendor_id: i32, product_id: i32) -> String {
+    if driver_type != GENERIC_SERIAL_TYPE {
+        return driver_type;
+    }
+    if let Some(refined) = USB_DRIVER_TYPES.get(&(vendor_id, product_id)) {
+        return refined.to_string();
+    }
+    if let Some(refined) = USB_VENDOR_DRIVER_TYPES.get(&vendor_id) {
+        return refined.to_string();
+    }
+    driver_type
+}
+
+/// Finds type of a TTY driver corresponding to a given major and minor numbers of the device
+/// node, and enumerates device nodes by driver type or looks them up by name.
 #[mockall::automock]
 pub trait DriverTypeFinder {
     /// Find driver type by the device node path /dev/name.
     fn find(&self, devnode_path: &Path) -> Result<String>;
+
+    /// Like `find`, but also returns the subtype after the colon in `/proc/tty/drivers`, e.g.
+    /// `slave`/`master` for `pty:slave`/`pty:master`, or `console` for `system:console`. `None`
+    /// if the driver's type has no subtype, e.g. the plain `serial` entries.
+    fn find_full(&self, devnode_path: &Path) -> Result<(String, Option<String>)>;
+
+    /// Returns the full `DriverInfo` of the driver backing `/dev/<name>`.
+    fn find_by_name(&self, name: &str) -> Result<DriverInfo>;
+
+    /// Walks `/dev` and `/dev/pts`, returning the path of every device node whose driver type is
+    /// `driver_type`, e.g. `list("serial")` for every serial device node currently present.
+    fn list(&self, driver_type: &str) -> Result<Vec<PathBuf>>;
+
+    /// Drops the cached contents of `/proc/tty/drivers`, forcing the next lookup to re-read it.
+    /// Called by `DeviceEventsHandler` on every device hotplug event, since a USB-serial module
+    /// registering or tearing down a driver happens exactly on the same probe/disconnect
+    /// lifecycle as the device node itself appearing or disappearing.
+    fn invalidate_cache(&self);
 }
 
 /// Implements DriverTypeFinder
@@ -40,12 +98,18 @@ pub struct DriverTypeFinderImpl {
     /// The path to the file with drivers info, i.e. /proc/tty/drivers (except for tests).
     drivers_file_path: PathBuf,
 
+    /// The directory device nodes live under, i.e. /dev (except for tests); `list` and
+    /// `find_by_name` resolve device nodes relative to this and its `pts` subdirectory.
+    dev_root: PathBuf,
+
     /// Cached content of /proc/tty/drivers, we re-read it when some driver is not found.
     drivers_cache: RefCell<Vec<DriverInfo>>,
 }
 
+/// Info about a single TTY driver entry parsed from `/proc/tty/drivers`.
 #[cfg_attr(test, derive(Debug, PartialEq))]
-struct DriverInfo {
+#[derive(Clone)]
+pub struct DriverInfo {
     /// Major device number supported by the driver.
     pub major: u32,
 
@@ -54,13 +118,59 @@ struct DriverInfo {
 
     /// The type of the driver: "serial", "console", "system", "pty".
     pub driver_type: String,
+
+    /// The subtype after the colon in `/proc/tty/drivers`, e.g. `slave`/`master` for a `pty`
+    /// driver, or `console` for `system:console`. `None` if the type has no subtype.
+    pub subtype: Option<String>,
 }
 
 impl DriverTypeFinder for DriverTypeFinderImpl {
     fn find(&self, devnode_path: &Path) -> Result<String> {
+        Ok(self.find_full(devnode_path)?.0)
+    }
+
+    fn find_full(&self, devnode_path: &Path) -> Result<(String, Option<String>)> {
         let (major, minor) = Self::get_devnum(devnode_path)?;
         self.find_by_devnum(major, minor)
     }
+
+    fn find_by_name(&self, name: &str) -> Result<DriverInfo> {
+        let (major, minor) = Self::get_devnum(&self.dev_root.join(name))?;
+        self.find_info_by_devnum(major, minor)
+    }
+
+    fn list(&self, driver_type: &str) -> Result<Vec<PathBuf>> {
+        self.ensure_drivers_loaded()?;
+        let mut nodes = Vec::new();
+        for dir in [self.dev_root.clone(), self.dev_root.join("pts")] {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Ok(metadata) = fs::metadata(&path) else {
+                    continue;
+                };
+                let devnum = metadata.rdev() as libc::dev_t;
+                let (Ok(major), Ok(minor)) =
+                    (u32::try_from(libc::major(devnum)), u32::try_from(libc::minor(devnum)))
+                else {
+                    continue;
+                };
+                let matches = self.drivers_cache.borrow().iter().any(|d| {
+                    d.driver_type == driver_type && d.major == major && d.minor_range.contains(&minor)
+                });
+                if matches {
+                    nodes.push(path);
+                }
+            }
+        }
+        Ok(nodes)
+    }
+
+    fn invalidate_cache(&self) {
+        self.drivers_cache.borrow_mut().clear();
+    }
 }
 
 impl DriverTypeFinderImpl {
@@ -69,7 +179,11 @@ impl DriverTypeFinderImpl {
     }
 
     fn new_from_file(drivers_file_path: PathBuf) -> Self {
-        Self { drivers_file_path, drivers_cache: RefCell::default() }
+        Self::new_from_file_and_dev_root(drivers_file_path, Path::new("/dev").to_path_buf())
+    }
+
+    fn new_from_file_and_dev_root(drivers_file_path: PathBuf, dev_root: PathBuf) -> Self {
+        Self { drivers_file_path, dev_root, drivers_cache: RefCell::default() }
     }
 
     fn get_devnum(devnode_path: &Path) -> Result<(u32, u32)> {
@@ -79,7 +193,12 @@ impl DriverTypeFinderImpl {
         Ok((major, minor))
     }
 
-    fn find_by_devnum(&self, major: u32, minor: u32) -> Result<String> {
+    fn find_by_devnum(&self, major: u32, minor: u32) -> Result<(String, Option<String>)> {
+        let info = self.find_info_by_devnum(major, minor)?;
+        Ok((info.driver_type, info.subtype))
+    }
+
+    fn find_info_by_devnum(&self, major: u32, minor: u32) -> Result<DriverInfo> {
         let result = self.find_in_cache(major, minor);
         if result.is_ok() {
             return result;
@@ -90,15 +209,23 @@ impl DriverTypeFinderImpl {
         self.find_in_cache(major, minor)
     }
 
-    fn find_in_cache(&self, major: u32, minor: u32) -> Result<String> {
+    fn find_in_cache(&self, major: u32, minor: u32) -> Result<DriverInfo> {
         self.drivers_cache
             .borrow()
             .iter()
             .find(|d| d.major == major && d.minor_range.contains(&minor))
-            .map(|d| d.driver_type.clone())
+            .cloned()
             .ok_or(anyhow!("TTY driver with numbers {}, {} not found", major, minor))
     }
 
+    /// Populates `drivers_cache` from `drivers_file_path` if it hasn't been read yet.
+    fn ensure_drivers_loaded(&self) -> Result<()> {
+        if self.drivers_cache.borrow().is_empty() {
+            self.read_drivers_from_file()?;
+        }
+        Ok(())
+    }
+
     fn read_drivers_from_file(&self) -> Result<()> {
         let mut drivers = self.drivers_cache.borrow_mut();
         drivers.clear();
@@ -110,8 +237,10 @@ impl DriverTypeFinderImpl {
             if parts.len() != 5 {
                 bail!("Wrong number of fields in the line '{}'", line);
             }
+            let mut type_parts = parts[4].split(":");
             drivers.push(DriverInfo {
-                driver_type: parts[4].split(":").next().unwrap().to_string(),
+                driver_type: type_parts.next().unwrap().to_string(),
+                subtype: type_parts.next().map(str::to_string),
                 major: parts[2].parse()?,
                 minor_range: {
                     let mut minor = parts[3].split("-");
@@ -128,6 +257,8 @@ impl DriverTypeFinderImpl {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use nix::sys::stat::{makedev, mknod, Mode, SFlag};
+    use std::collections::HashSet;
     use tempfile::{tempdir, TempDir};
 
     const REAL_DRIVERS_FILE_CONTENTS: &str = r"
@@ -149,7 +280,18 @@ pty_master           /dev/ptm      128 0-1048575 pty:master
 
         let result = DriverTypeFinderImpl::new_from_file(filename).find_by_devnum(4, 95)?;
 
-        assert_eq!(result, "serial");
+        assert_eq!(result, ("serial".to_string(), None));
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_in_real_file_pty_slave_has_subtype() -> Result<()> {
+        let dir = tempdir()?;
+        let filename = create_drivers_file(&dir, REAL_DRIVERS_FILE_CONTENTS)?;
+
+        let result = DriverTypeFinderImpl::new_from_file(filename).find_by_devnum(136, 0)?;
+
+        assert_eq!(result, ("pty".to_string(), Some("slave".to_string())));
         Ok(())
     }
 
@@ -191,4 +333,96 @@ pty_master           /dev/ptm      128 0-1048575 pty:master
         std::fs::write(&filename, content)?;
         Ok(filename)
     }
+
+    #[test]
+    fn test_find_by_name() -> Result<()> {
+        let dir = tempdir()?;
+        let filename = create_drivers_file(&dir, REAL_DRIVERS_FILE_CONTENTS)?;
+        let dev_root = dir.path().join("dev");
+        fs::create_dir(&dev_root)?;
+        create_char_device(&dev_root.join("ttyACM0"), 166, 0)?;
+
+        let result = DriverTypeFinderImpl::new_from_file_and_dev_root(filename, dev_root)
+            .find_by_name("ttyACM0")?;
+
+        assert_eq!(result.driver_type, "serial");
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_by_name_missing_node() -> Result<()> {
+        let dir = tempdir()?;
+        let filename = create_drivers_file(&dir, REAL_DRIVERS_FILE_CONTENTS)?;
+        let dev_root = dir.path().join("dev");
+        fs::create_dir(&dev_root)?;
+
+        let result =
+            DriverTypeFinderImpl::new_from_file_and_dev_root(filename, dev_root).find_by_name("ttyACM0");
+
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_finds_nodes_in_dev_and_pts() -> Result<()> {
+        let dir = tempdir()?;
+        let filename = create_drivers_file(&dir, REAL_DRIVERS_FILE_CONTENTS)?;
+        let dev_root = dir.path().join("dev");
+        fs::create_dir(&dev_root)?;
+        fs::create_dir(dev_root.join("pts"))?;
+        create_char_device(&dev_root.join("ttyACM0"), 166, 0)?;
+        create_char_device(&dev_root.join("ttyS0"), 4, 64)?;
+        create_char_device(&dev_root.join("ttynull"), 240, 0)?;
+        create_char_device(&dev_root.join("pts").join("0"), 136, 0)?;
+
+        let finder = DriverTypeFinderImpl::new_from_file_and_dev_root(filename, dev_root.clone());
+        let nodes = finder.list("serial")?;
+
+        assert_eq!(
+            nodes.into_iter().collect::<HashSet<_>>(),
+            HashSet::from([dev_root.join("ttyACM0"), dev_root.join("ttyS0")])
+        );
+        assert_eq!(finder.list("pty")?, vec![dev_root.join("pts").join("0")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_no_matches_is_empty() -> Result<()> {
+        let dir = tempdir()?;
+        let filename = create_drivers_file(&dir, REAL_DRIVERS_FILE_CONTENTS)?;
+        let dev_root = dir.path().join("dev");
+        fs::create_dir(&dev_root)?;
+        create_char_device(&dev_root.join("ttynull"), 240, 0)?;
+
+        let nodes = DriverTypeFinderImpl::new_from_file_and_dev_root(filename, dev_root)
+            .list("serial")?;
+
+        assert!(nodes.is_empty());
+        Ok(())
+    }
+
+    fn create_char_device(path: &Path, major: u32, minor: u32) -> Result<()> {
+        mknod(path, SFlag::S_IFCHR, Mode::from_bits_truncate(0o600), makedev(major.into(), minor.into()))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_refine_driver_type_exact_match() {
+        assert_eq!(refine_driver_type("serial".to_string(), 0x10C4, 0xEA60), "cp210x");
+    }
+
+    #[test]
+    fn test_refine_driver_type_vendor_only_match() {
+        assert_eq!(refine_driver_type("serial".to_string(), 0x1A86, -1), "ch340");
+    }
+
+    #[test]
+    fn test_refine_driver_type_unknown_ids_untouched() {
+        assert_eq!(refine_driver_type("serial".to_string(), 0x1234, 0x5678), "serial");
+    }
+
+    #[test]
+    fn test_refine_driver_type_non_generic_untouched() {
+        assert_eq!(refine_driver_type("console".to_string(), 0x10C4, 0xEA60), "console");
+    }
 }
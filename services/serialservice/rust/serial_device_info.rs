@@ -0,0 +1,203 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Resolves the USB/sysfs identity of a serial device node, so a client can pick a specific
+//! physical adapter by its vendor/product ID and serial number rather than its `/dev/ttyUSBn`-
+//! style index, which the kernel USB-serial layer assigns non-deterministically across
+//! reconnects.
+
+use android_hardware_serialservice::aidl::android::hardware::serialservice::{
+    BusType::BusType, SerialDeviceInfo::SerialDeviceInfo,
+};
+use crate::sysfs_attr::{read_hex_attr, read_string_attr};
+use anyhow::Result;
+use std::path::Path;
+use ueventd::device::Device;
+
+/// Sysfs directory backing each TTY device node, e.g. `/sys/class/tty/ttyACM0`.
+const TTY_CLASS_DIR: &str = "/sys/class/tty";
+
+/// Root of the sysfs hierarchy, used to resolve a `Device` from a `/sys/class/tty/<name>` path.
+const SYSFS_ROOT: &str = "/sys";
+
+/// Buses checked, in this order, when walking a device's ancestry for its identity. `usb` is
+/// checked first since it's the common case this resolver exists for; `pci` is checked last so
+/// that a USB device whose root hub happens to sit on a PCI host controller is still reported as
+/// `USB`, not `PCI`.
+const BUSES: [(&str, BusType); 3] =
+    [("usb", BusType::USB), ("platform", BusType::PLATFORM), ("pci", BusType::PCI)];
+
+/// Resolves the `SerialDeviceInfo` for the TTY device node named `name` (e.g. `ttyACM0`), by
+/// walking up its `/sys/class/tty/<name>/device` ancestry looking for a bus this resolver
+/// recognizes. Returns `BusType.UNKNOWN` with no identity if none is found, e.g. for a `virtual`
+/// device with no backing hardware.
+pub fn resolve(name: &str) -> Result<SerialDeviceInfo> {
+    let syspath = Path::new(TTY_CLASS_DIR).join(name);
+    let device = Device::with_root_and_syspath(Path::new(SYSFS_ROOT), &syspath)?;
+    Ok(resolve_for_device(&device))
+}
+
+fn resolve_for_device(device: &Device) -> SerialDeviceInfo {
+    for (subsystem, bus_type) in BUSES {
+        if let Some(info) = find_on_bus(device, subsystem, bus_type) {
+            return info;
+        }
+    }
+    unknown_info()
+}
+
+/// Walks `device`'s ancestors restricted to `subsystem`, returning the first one that yields an
+/// identity for `bus_type`. `platform` ancestors have no standard vendor/product attributes, so
+/// the nearest one found is reported as-is, with an empty identity.
+fn find_on_bus(device: &Device, subsystem: &str, bus_type: BusType) -> Option<SerialDeviceInfo> {
+    let mut current_opt = device.parent_with_subsystem(subsystem);
+    while let Some(current) = current_opt {
+        let attrs = current.sysattrs();
+        match bus_type {
+            BusType::USB => {
+                if let Ok(vendor_id) = read_hex_attr(&attrs, "idVendor") {
+                    return Some(SerialDeviceInfo {
+                        vendorId: vendor_id,
+                        productId: read_hex_attr(&attrs, "idProduct").unwrap_or(-1),
+                        manufacturer: read_string_attr(&attrs, "manufacturer"),
+                        serialNumber: read_string_attr(&attrs, "serial"),
+                        busType: BusType::USB,
+                    });
+                }
+            }
+            BusType::PCI => {
+                if let Ok(vendor_id) = read_hex_attr(&attrs, "vendor") {
+                    return Some(SerialDeviceInfo {
+                        vendorId: vendor_id,
+                        productId: read_hex_attr(&attrs, "device").unwrap_or(-1),
+                        manufacturer: String::new(),
+                        serialNumber: String::new(),
+                        busType: BusType::PCI,
+                    });
+                }
+            }
+            BusType::PLATFORM => {
+                return Some(SerialDeviceInfo {
+                    vendorId: -1,
+                    productId: -1,
+                    manufacturer: String::new(),
+                    serialNumber: String::new(),
+                    busType: BusType::PLATFORM,
+                });
+            }
+            _ => {}
+        }
+        current_opt = current.parent_with_subsystem(subsystem);
+    }
+    None
+}
+
+fn unknown_info() -> SerialDeviceInfo {
+    SerialDeviceInfo {
+        vendorId: -1,
+        productId: -1,
+        manufacturer: String::new(),
+        serialNumber: String::new(),
+        busType: BusType::UNKNOWN,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs_test_fixtures::create_usb_device_in_mock_sysfs;
+    use std::collections::HashMap;
+    use ueventd::mock_sysfs::{MockSysfs, SysfsFile};
+
+    #[test]
+    fn test_resolve_usb_device() {
+        let (device, _sysfs_dir) = create_usb_device_in_mock_sysfs(false);
+
+        let info = resolve_for_device(&device);
+
+        assert_eq!(info.busType, BusType::USB);
+        assert_eq!(info.vendorId, 0x0694);
+        assert_eq!(info.productId, 0x0009);
+        assert_eq!(info.manufacturer, "Acme Corp".to_string());
+        assert_eq!(info.serialNumber, "ABC123".to_string());
+    }
+
+    #[test]
+    fn test_resolve_platform_device() {
+        let (device, _sysfs_dir) = create_platform_device_in_mock_sysfs();
+
+        let info = resolve_for_device(&device);
+
+        assert_eq!(info.busType, BusType::PLATFORM);
+        assert_eq!(info.vendorId, -1);
+        assert_eq!(info.productId, -1);
+    }
+
+    #[test]
+    fn test_resolve_unrecognized_bus_is_unknown() {
+        let (device, _sysfs_dir) = create_virtual_device_in_mock_sysfs();
+
+        let info = resolve_for_device(&device);
+
+        assert_eq!(info.busType, BusType::UNKNOWN);
+        assert_eq!(info.vendorId, -1);
+        assert_eq!(info.productId, -1);
+        assert_eq!(info.manufacturer, "".to_string());
+        assert_eq!(info.serialNumber, "".to_string());
+    }
+
+    fn create_platform_device_in_mock_sysfs() -> (Device, MockSysfs) {
+        let sysfs = SysfsFile::Dir(HashMap::from([
+            (
+                "devices/platform/serial8250",
+                SysfsFile::Dir(HashMap::from([
+                    (
+                        "tty/ttyS1",
+                        SysfsFile::Dir(HashMap::from([
+                            ("subsystem", SysfsFile::Symlink("../../../../../class/tty")),
+                            ("uevent", SysfsFile::RegularFile("")),
+                        ])),
+                    ),
+                    ("subsystem", SysfsFile::Symlink("../../../bus/platform")),
+                    ("uevent", SysfsFile::RegularFile("")),
+                ])),
+            ),
+            ("bus/platform", SysfsFile::Dir(HashMap::new())),
+            ("class/tty", SysfsFile::Dir(HashMap::new())),
+        ]));
+        let sysfs_dir = MockSysfs::new(sysfs).expect("Could not create mock sysfs");
+        let sysfs_path = sysfs_dir.path().join("devices/platform/serial8250/tty/ttyS1");
+        let device = Device::with_root_and_syspath(sysfs_dir.path(), &sysfs_path).unwrap();
+        (device, sysfs_dir)
+    }
+
+    fn create_virtual_device_in_mock_sysfs() -> (Device, MockSysfs) {
+        let sysfs = SysfsFile::Dir(HashMap::from([
+            (
+                "devices/virtual/tty/ttyGS0",
+                SysfsFile::Dir(HashMap::from([
+                    ("subsystem", SysfsFile::Symlink("../../../../class/tty")),
+                    ("uevent", SysfsFile::RegularFile("")),
+                ])),
+            ),
+            ("class/tty", SysfsFile::Dir(HashMap::new())),
+        ]));
+        let sysfs_dir = MockSysfs::new(sysfs).expect("Could not create mock sysfs");
+        let sysfs_path = sysfs_dir.path().join("devices/virtual/tty/ttyGS0");
+        let device = Device::with_root_and_syspath(sysfs_dir.path(), &sysfs_path).unwrap();
+        (device, sysfs_dir)
+    }
+}
@@ -18,8 +18,10 @@
 //! open serial ports.
 
 use android_hardware_serialservice::aidl::android::hardware::serialservice::{
-    ISerialManager::ISerialManagerAsyncServer, ISerialPortListener::ISerialPortListener,
-    SerialPortInfo::SerialPortInfo,
+    FlowControl::FlowControl, ISerialManager::ISerialManagerAsyncServer,
+    ISerialPortListener::ISerialPortListener, Parity::Parity,
+    SerialDeviceInfo::SerialDeviceInfo, SerialPortConfiguration::SerialPortConfiguration,
+    SerialPortFilter::SerialPortFilter, SerialPortInfo::SerialPortInfo, WindowSize::WindowSize,
 };
 use android_hardware_serialservice::binder;
 use async_trait::async_trait;
@@ -27,24 +29,33 @@ use binder::{
     DeathRecipient, ExceptionCode, ParcelFileDescriptor, Result, SpIBinder, Status, Strong,
     ThreadState,
 };
+use futures::stream::BoxStream;
 use futures::StreamExt;
 use nix::libc;
+use nix::sys::termios::{self, BaudRate, ControlFlags, InputFlags};
 use rustutils::users::{AID_ROOT, AID_SYSTEM};
 use std::collections::{BTreeMap, HashMap};
 use std::fs::OpenOptions;
-use std::io::Write;
-use std::os::fd::AsRawFd;
+use std::io::{Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
 use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::net::UnixStream;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
 use ueventd::device_node::watcher::Watcher;
+use ueventd::event::DeviceEvent;
 
 use crate::device_events_handler::{DeviceEventCallback, DeviceEventsHandler};
 use crate::driver_type_finder::{DriverTypeFinder, DriverTypeFinderImpl};
+use crate::serial_port_policy::SerialPortPolicy;
 
 // This function is wrapped in a module because ioctl_none_bad! macro generates a `pub` function.
 mod raw {
     use nix::ioctl_none_bad;
+    use nix::ioctl_read_bad;
+    use nix::ioctl_write_ptr_bad;
     use nix::libc;
 
     // Puts the terminal into exclusive mode.
@@ -52,6 +63,16 @@ mod raw {
     ioctl_none_bad!(tiocexcl, libc::TIOCEXCL);
     // Disable exclusive mode.
     ioctl_none_bad!(tiocnxcl, libc::TIOCNXCL);
+    // Reads the state of the modem control lines into a TIOCM_* bitmask.
+    ioctl_read_bad!(tiocmget, libc::TIOCMGET, i32);
+    // Sets the modem control lines named in the given TIOCM_* bitmask.
+    ioctl_write_ptr_bad!(tiocmbis, libc::TIOCMBIS, i32);
+    // Clears the modem control lines named in the given TIOCM_* bitmask.
+    ioctl_write_ptr_bad!(tiocmbic, libc::TIOCMBIC, i32);
+    // Reads the terminal's window size (rows/cols/xpixel/ypixel).
+    ioctl_read_bad!(tiocgwinsz, libc::TIOCGWINSZ, libc::winsize);
+    // Sets the terminal's window size.
+    ioctl_write_ptr_bad!(tiocswinsz, libc::TIOCSWINSZ, libc::winsize);
 }
 
 /// The `ISerialManager` implementation.
@@ -59,35 +80,286 @@ mod raw {
 pub struct SerialManager {
     serial_ports: Arc<Mutex<HashMap<String, SerialPortInfo>>>,
     listeners: Arc<Mutex<BTreeMap<SpIBinder, ListenerEntry>>>,
+    forwarders: Arc<Mutex<HashMap<String, ForwardHandle>>>,
+    stored_configs: Arc<Mutex<HashMap<String, SerialPortConfiguration>>>,
+    persistent_ports: Arc<Mutex<std::collections::HashSet<String>>>,
+    pending_persistent: Arc<Mutex<HashMap<PersistentIdentity, PendingPersistentPort>>>,
+    brokers: Arc<Mutex<HashMap<String, BrokerHandle>>>,
+    policy: Arc<dyn SerialPortPolicy + Send + Sync>,
+}
+
+/// A port that was marked persistent and has since been unplugged, waiting to be matched up
+/// with a reappearing device with the same vendor/product ID and serial number.
+struct PendingPersistentPort {
+    name: String,
+    config: Option<SerialPortConfiguration>,
+}
+
+/// Key used to match a reappearing device against a `PendingPersistentPort`: vendor ID, product
+/// ID, and serial number. A device with no matchable identity (vid/pid both -1 and no serial,
+/// e.g. a platform/UART node with no USB descriptors) has no `PersistentIdentity` at all, since
+/// `(-1, -1, "")` would otherwise conflate every such device with every other one.
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct PersistentIdentity {
+    vendor_id: i32,
+    product_id: i32,
+    serial_number: String,
+}
+
+impl PersistentIdentity {
+    /// Returns `info`'s `PersistentIdentity`, or `None` if `info` has no stable, matchable
+    /// identity to key a persistent reattach on (vid/pid both unset and no serial number).
+    fn for_port(info: &SerialPortInfo) -> Option<Self> {
+        if info.vendorId == -1 && info.productId == -1 && info.serialNumber.is_empty() {
+            return None;
+        }
+        Some(Self {
+            vendor_id: info.vendorId,
+            product_id: info.productId,
+            serial_number: info.serialNumber.clone(),
+        })
+    }
 }
 
 struct ListenerEntry {
     listener: Strong<dyn ISerialPortListener>,
+    filter: SerialPortFilter,
     _death_recipient: DeathRecipient,
 }
 
+/// A `SerialPortFilter` with every field left at its "match anything" value.
+fn match_all_filter() -> SerialPortFilter {
+    SerialPortFilter {
+        vendorId: -1,
+        productId: -1,
+        subsystem: String::new(),
+        driverType: String::new(),
+    }
+}
+
+/// Returns whether `info` satisfies every criterion set in `filter`. A field left at its
+/// "unset" value (-1 for the IDs, empty string for the strings) matches any port.
+fn matches_filter(filter: &SerialPortFilter, info: &SerialPortInfo) -> bool {
+    (filter.vendorId == -1 || filter.vendorId == info.vendorId)
+        && (filter.productId == -1 || filter.productId == info.productId)
+        && (filter.subsystem.is_empty() || filter.subsystem == info.subsystem)
+        && (filter.driverType.is_empty() || filter.driverType == info.driverType)
+}
+
+/// A running TCP forward for one serial port, started by `startForward`.
+struct ForwardHandle {
+    endpoint: String,
+    accept_task: JoinHandle<()>,
+    /// One entry per in-flight `pump_forward_connection` call, keyed by a per-`ForwardHandle`
+    /// counter. Each entry removes itself once its connection ends on its own; `Drop` aborts
+    /// whatever's left so `stopForward`/device removal actually tears down connections still in
+    /// flight instead of leaving them running until the remote end hits EOF.
+    connection_tasks: Arc<Mutex<HashMap<u64, JoinHandle<()>>>>,
+}
+
+impl Drop for ForwardHandle {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+        for task in self.connection_tasks.lock().unwrap().values() {
+            task.abort();
+        }
+    }
+}
+
+/// A running multiplexing broker for one serial port, started by `requestShared`. The broker
+/// owns the single real serial fd; each connected client is a `UnixStream` end whose reads are
+/// fanned out from the port and whose writes are serialized onto the port via `port_writer`.
+struct BrokerHandle {
+    clients: Arc<Mutex<HashMap<i32, UnixStream>>>,
+    port_writer: Arc<Mutex<std::fs::File>>,
+    reader_task: JoinHandle<()>,
+    /// Read end of a pipe whose write end (`_shutdown_write`) is only ever closed by dropping
+    /// this `BrokerHandle`. The port reader and every per-client reader poll this fd alongside
+    /// their own, so teardown wakes their blocking `read()`s promptly instead of leaving them
+    /// parked until the port or client fd next yields data on its own.
+    shutdown_read: Arc<std::fs::File>,
+    _shutdown_write: std::fs::File,
+}
+
+impl BrokerHandle {
+    /// Registers a new client socket with the broker: adds it to the fan-out set and spawns a
+    /// blocking task that forwards everything the client writes onto the real port. When the
+    /// client disconnects, it is dropped from `clients` and, if it was the last one, `on_empty`
+    /// is invoked so the caller can tear the broker down. The reader also wakes, and exits
+    /// without touching `clients`/`on_empty`, if the broker itself is torn down first.
+    fn add_client(&self, client: UnixStream, on_empty: impl FnOnce() + Send + 'static) {
+        let Ok(reader) = client.try_clone() else { return };
+        if reader.set_nonblocking(true).is_err() {
+            return;
+        }
+        let reader_fd = reader.as_raw_fd();
+        let fd = client.as_raw_fd();
+        self.clients.lock().unwrap().insert(fd, client);
+        let port_writer = self.port_writer.clone();
+        let clients = self.clients.clone();
+        let shutdown_read = self.shutdown_read.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut reader = reader;
+            let mut buf = [0u8; 4096];
+            let shut_down = loop {
+                if !wait_for_read_or_shutdown(reader_fd, shutdown_read.as_raw_fd()) {
+                    break true;
+                }
+                match reader.read(&mut buf) {
+                    Ok(0) => break false,
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                    Err(_) => break false,
+                    Ok(n) => {
+                        if port_writer.lock().unwrap().write_all(&buf[..n]).is_err() {
+                            break false;
+                        }
+                    }
+                }
+            };
+            if shut_down {
+                return;
+            }
+            let is_empty = {
+                let mut clients = clients.lock().unwrap();
+                clients.remove(&fd);
+                clients.is_empty()
+            };
+            if is_empty {
+                on_empty();
+            }
+        });
+    }
+}
+
+impl Drop for BrokerHandle {
+    fn drop(&mut self) {
+        self.reader_task.abort();
+    }
+}
+
+/// Blocks until `fd` is readable or `shutdown_fd` is readable (its write end having closed, which
+/// signals teardown). Returns `true` if `fd` is the one ready to read, `false` if shutdown fired
+/// first and the caller should stop without reading `fd` again.
+fn wait_for_read_or_shutdown(fd: i32, shutdown_fd: i32) -> bool {
+    let mut fds = [
+        libc::pollfd { fd, events: libc::POLLIN, revents: 0 },
+        libc::pollfd { fd: shutdown_fd, events: libc::POLLIN, revents: 0 },
+    ];
+    loop {
+        // SAFETY: `fds` points to a valid, correctly sized array of `pollfd` for the duration of
+        // the call.
+        let ret = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) };
+        if ret >= 0 {
+            break;
+        }
+        if std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+            return false;
+        }
+    }
+    fds[1].revents == 0
+}
+
+/// Creates a pipe used purely as a shutdown signal: the write end is held by `BrokerHandle` and
+/// closed only when it is dropped, at which point `read_end` wakes up in `poll()` for every
+/// reader task still waiting on it.
+fn shutdown_pipe() -> std::io::Result<(std::fs::File, std::fs::File)> {
+    let mut fds = [0i32; 2];
+    // SAFETY: `fds` is a valid 2-element array to receive the pipe's read/write fds.
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: `fds[0]` and `fds[1]` are freshly created, uniquely owned fds from `pipe2` above.
+    Ok(unsafe { (std::fs::File::from_raw_fd(fds[0]), std::fs::File::from_raw_fd(fds[1])) })
+}
+
 impl SerialManager {
-    /// Creates an instance of `SerialManager` and starts `DeviceEventsHandler`.
-    pub async fn new() -> Self {
+    /// Creates an instance of `SerialManager` and starts `DeviceEventsHandler`. `policy` gates
+    /// which ports are visible through discovery and which may be opened; the same instance is
+    /// consulted in both places so a port disallowed by policy never appears to clients at all.
+    pub async fn new(policy: Arc<dyn SerialPortPolicy + Send + Sync>) -> Self {
         let instance = SerialManager {
             serial_ports: Mutex::new(HashMap::new()).into(),
             listeners: Mutex::new(BTreeMap::new()).into(),
+            forwarders: Mutex::new(HashMap::new()).into(),
+            stored_configs: Mutex::new(HashMap::new()).into(),
+            persistent_ports: Mutex::new(std::collections::HashSet::new()).into(),
+            pending_persistent: Mutex::new(HashMap::new()).into(),
+            brokers: Mutex::new(HashMap::new()).into(),
+            policy: policy.clone(),
         };
-        let (mut watcher, stream) = Watcher::new().await.expect("failed to watch /dev");
-        tokio::spawn(async move {
-            watcher.run_event_loop().await;
-        });
+        let driver_type_finder =
+            Arc::new(Mutex::new(DriverTypeFinderImpl::new())) as Arc<Mutex<dyn DriverTypeFinder + Send>>;
+        // `DeviceEventsHandler` invalidates `driver_type_finder`'s cache itself on every hotplug
+        // event it receives over the same netlink-backed ueventd stream, so a driver registered
+        // or torn down alongside the device is picked up immediately; no separate watcher on
+        // `/proc/tty/drivers` is needed (and inotify on that file wouldn't fire anyway, since
+        // procfs doesn't emit fsnotify events for its synthetic files).
         DeviceEventsHandler::start_new(
-            stream.boxed(),
+            Box::new(new_watcher_stream),
             Box::new(instance.clone()) as Box<dyn DeviceEventCallback + Send>,
-            Arc::new(Mutex::new(DriverTypeFinderImpl::new()))
-                as Arc<Mutex<dyn DriverTypeFinder + Send>>,
+            driver_type_finder,
+            policy,
         )
         .await;
         instance
     }
 }
 
+/// Builds a fresh ueventd-backed event stream, starting a new `Watcher` and its background event
+/// loop. Used as `DeviceEventsHandler`'s reconnect factory so a closed or crashed watcher socket
+/// is re-established transparently instead of leaving the handler without hotplug events.
+///
+/// This is a sync fn returning a lazy stream rather than an async fn, since `StreamFactory` is
+/// `Fn() -> BoxStream<...>`; the `Watcher::new()` setup itself only runs once the returned stream
+/// is first polled, entirely on the calling task via plain `.await` (no `block_in_place`, which
+/// would panic on a `current_thread` runtime such as `service_fuzzer`'s).
+fn new_watcher_stream() -> BoxStream<'static, DeviceEvent> {
+    futures::stream::once(async {
+        let (mut watcher, stream) = Watcher::new().await.expect("failed to watch /dev");
+        tokio::spawn(async move {
+            watcher.run_event_loop().await;
+        });
+        stream
+    })
+    .flatten()
+    .boxed()
+}
+
+impl SerialManager {
+    /// Looks up `port_name`, returning an `ILLEGAL_ARGUMENT` `Status` if it is unknown, or a
+    /// `SECURITY` one if it has been filtered out by `policy` since it was discovered.
+    fn checked_port_info(&self, port_name: &str) -> std::result::Result<SerialPortInfo, Status> {
+        let info = self.serial_ports.lock().unwrap().get(port_name).cloned().ok_or_else(|| {
+            Status::new_exception_str(
+                ExceptionCode::ILLEGAL_ARGUMENT,
+                Some(format!("port_name {} does not exist", port_name)),
+            )
+        })?;
+        if !self.policy.is_visible(&info) {
+            return Err(Status::new_exception_str(
+                ExceptionCode::SECURITY,
+                Some(format!("port_name {} is not permitted by policy", port_name)),
+            ));
+        }
+        Ok(info)
+    }
+
+    /// Opens `port_name` for the duration of a single ioctl. Returns the `Status` of
+    /// `checked_port_info` if the port is unknown or not permitted, or a `SERVICE_SPECIFIC` one
+    /// if it can't be opened.
+    fn open_managed_port(&self, port_name: &str) -> std::result::Result<std::fs::File, Status> {
+        self.checked_port_info(port_name)?;
+        OpenOptions::new().read(true).write(true).custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK).open(
+            Path::new("/dev").join(port_name),
+        ).map_err(|e| {
+            Status::new_exception_str(
+                ExceptionCode::SERVICE_SPECIFIC,
+                Some(format!("open() failed, errno={}", e.raw_os_error().unwrap_or(0))),
+            )
+        })
+    }
+}
+
 impl binder::Interface for SerialManager {
     fn dump(
         &self,
@@ -103,6 +375,17 @@ impl binder::Interface for SerialManager {
             write(file, format!("   Vendor ID: {}\n", port.vendorId))?;
             write(file, format!("   Product ID: {}\n", port.productId))?;
         }
+        let forwarders = self.forwarders.lock().unwrap();
+        write(file, format!("Has {} active forward(s).\n", forwarders.len()))?;
+        for (port_name, forward) in forwarders.iter() {
+            write(file, format!("Forward {port_name} -> {}\n", forward.endpoint))?;
+        }
+        let brokers = self.brokers.lock().unwrap();
+        write(file, format!("Has {} active shared broker(s).\n", brokers.len()))?;
+        for (port_name, broker) in brokers.iter() {
+            let client_count = broker.clients.lock().unwrap().len();
+            write(file, format!("Broker {port_name}: {client_count} client(s)\n"))?;
+        }
         Ok(())
     }
 }
@@ -121,6 +404,14 @@ impl ISerialManagerAsyncServer for SerialManager {
     async fn registerSerialPortListener(
         &self,
         listener: &Strong<dyn ISerialPortListener>,
+    ) -> Result<()> {
+        self.registerSerialPortListenerWithFilter(listener, &match_all_filter()).await
+    }
+
+    async fn registerSerialPortListenerWithFilter(
+        &self,
+        listener: &Strong<dyn ISerialPortListener>,
+        filter: &SerialPortFilter,
     ) -> Result<()> {
         check_permissions()?;
         let binder = listener.as_binder();
@@ -133,7 +424,11 @@ impl ISerialManagerAsyncServer for SerialManager {
                     .map(|listeners| listeners.lock().unwrap().remove(&binder_clone));
             })
         };
-        let entry = ListenerEntry { listener: listener.clone(), _death_recipient: death_recipient };
+        let entry = ListenerEntry {
+            listener: listener.clone(),
+            filter: filter.clone(),
+            _death_recipient: death_recipient,
+        };
         if self.listeners.lock().unwrap().insert(binder, entry).is_some() {
             return Err(Status::new_service_specific_error_str(-1, Some("Duplicate listener")));
         }
@@ -159,12 +454,7 @@ impl ISerialManagerAsyncServer for SerialManager {
         exclusive: bool,
     ) -> Result<ParcelFileDescriptor> {
         check_permissions()?;
-        if !self.serial_ports.lock().unwrap().contains_key(port_name) {
-            return Err(Status::new_exception_str(
-                ExceptionCode::ILLEGAL_ARGUMENT,
-                Some(format!("port_name {} does not exist", port_name)),
-            ));
-        };
+        self.checked_port_info(port_name)?;
         let path = Path::new("/dev").join(port_name);
         match OpenOptions::new()
             .read(flags & (libc::O_RDONLY | libc::O_RDWR) != 0)
@@ -198,12 +488,597 @@ impl ISerialManagerAsyncServer for SerialManager {
             }
         }
     }
+
+    async fn requestShared(&self, port_name: &str) -> Result<ParcelFileDescriptor> {
+        check_permissions()?;
+        self.checked_port_info(port_name)?;
+
+        let (local, remote) = UnixStream::pair().map_err(|e| {
+            Status::new_exception_str(
+                ExceptionCode::SERVICE_SPECIFIC,
+                Some(format!("socketpair() failed, errno={}", e.raw_os_error().unwrap_or(0))),
+            )
+        })?;
+
+        let mut brokers = self.brokers.lock().unwrap();
+        if !brokers.contains_key(port_name) {
+            let broker = start_broker(self.brokers.clone(), port_name)?;
+            brokers.insert(port_name.to_string(), broker);
+        }
+        let brokers_registry = self.brokers.clone();
+        let port_name_for_cleanup = port_name.to_string();
+        brokers.get(port_name).unwrap().add_client(remote, move || {
+            brokers_registry.lock().unwrap().remove(&port_name_for_cleanup);
+        });
+
+        Ok(ParcelFileDescriptor::new(local))
+    }
+
+    async fn configurePort(
+        &self,
+        port_name: &str,
+        config: &SerialPortConfiguration,
+    ) -> Result<()> {
+        check_permissions()?;
+        self.checked_port_info(port_name)?;
+        let path = Path::new("/dev").join(port_name);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+            .open(&path)
+            .map_err(|e| {
+                Status::new_exception_str(
+                    ExceptionCode::SERVICE_SPECIFIC,
+                    Some(format!("open() failed, errno={}", e.raw_os_error().unwrap_or(0))),
+                )
+            })?;
+        apply_port_configuration(&file, config)?;
+        self.stored_configs.lock().unwrap().insert(port_name.to_string(), config.clone());
+        Ok(())
+    }
+
+    async fn getPortConfiguration(&self, port_name: &str) -> Result<SerialPortConfiguration> {
+        check_permissions()?;
+        let file = self.open_managed_port(port_name)?;
+        read_port_configuration(&file)
+    }
+
+    async fn getSerialDeviceInfo(&self, port_name: &str) -> Result<SerialDeviceInfo> {
+        check_permissions()?;
+        self.checked_port_info(port_name)?;
+        crate::serial_device_info::resolve(port_name).map_err(|e| {
+            Status::new_exception_str(
+                ExceptionCode::SERVICE_SPECIFIC,
+                Some(format!("Could not resolve device info for {port_name}: {e}")),
+            )
+        })
+    }
+
+    async fn startForward(&self, port_name: &str, endpoint: &str) -> Result<()> {
+        check_permissions()?;
+        self.checked_port_info(port_name)?;
+        if self.forwarders.lock().unwrap().contains_key(port_name) {
+            return Err(Status::new_service_specific_error_str(
+                -1,
+                Some(format!("Forward already active for {port_name}")),
+            ));
+        }
+        let listener = TcpListener::bind(endpoint).await.map_err(|e| {
+            Status::new_exception_str(
+                ExceptionCode::SERVICE_SPECIFIC,
+                Some(format!("bind({endpoint}) failed: {e}")),
+            )
+        })?;
+        let port_path = Path::new("/dev").join(port_name);
+        let port_name_for_task = port_name.to_string();
+        let connection_tasks: Arc<Mutex<HashMap<u64, JoinHandle<()>>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let accept_connection_tasks = connection_tasks.clone();
+        let accept_task = tokio::spawn(async move {
+            let mut next_connection_id = 0u64;
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _addr)) => {
+                        let port_path = port_path.clone();
+                        let port_name_for_conn = port_name_for_task.clone();
+                        let connection_id = next_connection_id;
+                        next_connection_id += 1;
+                        let connection_tasks = accept_connection_tasks.clone();
+                        let task = tokio::spawn(async move {
+                            if let Err(e) = pump_forward_connection(&port_path, stream).await {
+                                log::warn!(
+                                    "Forward connection for '{port_name_for_conn}' ended: {e}"
+                                );
+                            }
+                            connection_tasks.lock().unwrap().remove(&connection_id);
+                        });
+                        accept_connection_tasks.lock().unwrap().insert(connection_id, task);
+                    }
+                    Err(e) => {
+                        log::error!("accept() failed for forward of '{port_name_for_task}': {e}");
+                        break;
+                    }
+                }
+            }
+        });
+        self.forwarders.lock().unwrap().insert(port_name.to_string(), ForwardHandle {
+            endpoint: endpoint.to_string(),
+            accept_task,
+            connection_tasks,
+        });
+        Ok(())
+    }
+
+    async fn stopForward(&self, port_name: &str) -> Result<()> {
+        check_permissions()?;
+        self.forwarders.lock().unwrap().remove(port_name).ok_or_else(|| {
+            Status::new_service_specific_error_str(
+                -1,
+                Some(format!("No forward active for {port_name}")),
+            )
+        })?;
+        Ok(())
+    }
+
+    async fn setPersistent(&self, port_name: &str, persistent: bool) -> Result<()> {
+        check_permissions()?;
+        if !self.serial_ports.lock().unwrap().contains_key(port_name)
+            && !persistent_pending_has_name(&self.pending_persistent, port_name)
+        {
+            return Err(Status::new_exception_str(
+                ExceptionCode::ILLEGAL_ARGUMENT,
+                Some(format!("port_name {} does not exist", port_name)),
+            ));
+        }
+        let mut persistent_ports = self.persistent_ports.lock().unwrap();
+        if persistent {
+            persistent_ports.insert(port_name.to_string());
+        } else {
+            persistent_ports.remove(port_name);
+            self.pending_persistent.lock().unwrap().retain(|_, pending| pending.name != port_name);
+        }
+        Ok(())
+    }
+
+    async fn getModemLines(&self, port_name: &str) -> Result<i32> {
+        check_permissions()?;
+        let file = self.open_managed_port(port_name)?;
+        let mut lines: i32 = 0;
+        // SAFETY: `file` is a valid, open fd for the lifetime of this call, and `lines` is a
+        // valid, properly aligned out-pointer for the duration of the ioctl.
+        unsafe { raw::tiocmget(file.as_raw_fd(), &mut lines) }.map_err(|e| {
+            Status::new_exception_str(
+                ExceptionCode::SERVICE_SPECIFIC,
+                Some(format!("TIOCMGET ioctl() failed, errno={}", e as i32)),
+            )
+        })?;
+        Ok(lines)
+    }
+
+    async fn setModemLines(&self, port_name: &str, set_mask: i32, clear_mask: i32) -> Result<()> {
+        check_permissions()?;
+        let file = self.open_managed_port(port_name)?;
+        if set_mask != 0 {
+            // SAFETY: `file` is a valid, open fd for the lifetime of this call, and `set_mask` is
+            // a valid in-pointer for the duration of the ioctl.
+            unsafe { raw::tiocmbis(file.as_raw_fd(), &set_mask) }.map_err(|e| {
+                Status::new_exception_str(
+                    ExceptionCode::SERVICE_SPECIFIC,
+                    Some(format!("TIOCMBIS ioctl() failed, errno={}", e as i32)),
+                )
+            })?;
+        }
+        if clear_mask != 0 {
+            // SAFETY: `file` is a valid, open fd for the lifetime of this call, and `clear_mask`
+            // is a valid in-pointer for the duration of the ioctl.
+            unsafe { raw::tiocmbic(file.as_raw_fd(), &clear_mask) }.map_err(|e| {
+                Status::new_exception_str(
+                    ExceptionCode::SERVICE_SPECIFIC,
+                    Some(format!("TIOCMBIC ioctl() failed, errno={}", e as i32)),
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    async fn sendBreak(&self, port_name: &str, duration_ms: i32) -> Result<()> {
+        check_permissions()?;
+        let file = self.open_managed_port(port_name)?;
+        // SAFETY: `file` is a valid, open fd for the lifetime of this call. `tcsendbreak`
+        // does not retain the fd or allocate/deallocate memory that Rust owns.
+        let rc = unsafe { libc::tcsendbreak(file.as_raw_fd(), duration_ms) };
+        if rc < 0 {
+            return Err(Status::new_exception_str(
+                ExceptionCode::SERVICE_SPECIFIC,
+                Some(format!(
+                    "tcsendbreak() failed, errno={}",
+                    std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+                )),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether any pending persistent descriptor refers to `port_name`, used so
+/// `setPersistent` can still be called while a persistent port is mid-reattach.
+fn persistent_pending_has_name(
+    pending_persistent: &Mutex<HashMap<PersistentIdentity, PendingPersistentPort>>,
+    port_name: &str,
+) -> bool {
+    pending_persistent.lock().unwrap().values().any(|pending| pending.name == port_name)
+}
+
+// Pumps bytes bidirectionally between `stream` and the serial port at `port_path` until either
+// side hits EOF or disconnects. The serial port is opened fresh per connection and the copy runs
+// on the blocking thread pool since the port is a character device, not a socket.
+async fn pump_forward_connection(port_path: &Path, stream: TcpStream) -> std::io::Result<()> {
+    let std_stream = stream.into_std()?;
+    std_stream.set_nonblocking(false)?;
+    let port_path = port_path.to_path_buf();
+    tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+        let port = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(&port_path)?;
+        let mut port_reader = port.try_clone()?;
+        let mut port_writer = port;
+        let mut tcp_reader = std_stream.try_clone()?;
+        let mut tcp_writer = std_stream;
+
+        let upstream = std::thread::spawn(move || {
+            let _ = std::io::copy(&mut tcp_reader, &mut port_writer);
+        });
+        let _ = std::io::copy(&mut port_reader, &mut tcp_writer);
+        let _ = upstream.join();
+        Ok(())
+    })
+    .await??;
+    Ok(())
+}
+
+/// Applies `config` to an already-open serial port file, via `termios`. Shared by
+/// `configurePort` and the persistent-port reattach path, which reapplies the last config that
+/// was in effect before the device was unplugged.
+fn apply_port_configuration(
+    file: &std::fs::File,
+    config: &SerialPortConfiguration,
+) -> std::result::Result<(), Status> {
+    let mut attrs = termios::tcgetattr(file).map_err(|e| termios_error("tcgetattr", e))?;
+    termios::cfmakeraw(&mut attrs);
+
+    let baud_rate = baud_rate_from_value(config.baudRate)?;
+    termios::cfsetispeed(&mut attrs, baud_rate).map_err(|e| termios_error("cfsetispeed", e))?;
+    termios::cfsetospeed(&mut attrs, baud_rate).map_err(|e| termios_error("cfsetospeed", e))?;
+
+    attrs.control_flags.remove(ControlFlags::CSIZE);
+    attrs.control_flags.insert(data_bits_flag(config.dataBits)?);
+
+    match config.parity {
+        Parity::NONE => attrs.control_flags.remove(ControlFlags::PARENB | ControlFlags::PARODD),
+        Parity::EVEN => {
+            attrs.control_flags.insert(ControlFlags::PARENB);
+            attrs.control_flags.remove(ControlFlags::PARODD);
+        }
+        Parity::ODD => attrs.control_flags.insert(ControlFlags::PARENB | ControlFlags::PARODD),
+        parity => {
+            return Err(Status::new_exception_str(
+                ExceptionCode::ILLEGAL_ARGUMENT,
+                Some(format!("Unsupported parity {parity:?}")),
+            ));
+        }
+    }
+
+    if config.stopBits == 2 {
+        attrs.control_flags.insert(ControlFlags::CSTOPB);
+    } else {
+        attrs.control_flags.remove(ControlFlags::CSTOPB);
+    }
+
+    match config.flowControl {
+        FlowControl::NONE => {
+            attrs.control_flags.remove(ControlFlags::CRTSCTS);
+            attrs.input_flags.remove(InputFlags::IXON | InputFlags::IXOFF | InputFlags::IXANY);
+        }
+        FlowControl::HARDWARE => attrs.control_flags.insert(ControlFlags::CRTSCTS),
+        FlowControl::SOFTWARE => {
+            attrs.input_flags.insert(InputFlags::IXON | InputFlags::IXOFF | InputFlags::IXANY)
+        }
+        flow_control => {
+            return Err(Status::new_exception_str(
+                ExceptionCode::ILLEGAL_ARGUMENT,
+                Some(format!("Unsupported flow control {flow_control:?}")),
+            ));
+        }
+    }
+
+    // Always keep the port local and allow it to be read from, regardless of what the
+    // raw mode defaults to.
+    attrs.control_flags.insert(ControlFlags::CLOCAL | ControlFlags::CREAD);
+
+    termios::tcsetattr(file, termios::SetArg::TCSANOW, &attrs)
+        .map_err(|e| termios_error("tcsetattr", e))?;
+
+    apply_window_size(file, &config.windowSize)?;
+    Ok(())
+}
+
+/// Applies `window_size` to an already-open serial port file via `TIOCSWINSZ`.
+fn apply_window_size(
+    file: &std::fs::File,
+    window_size: &WindowSize,
+) -> std::result::Result<(), Status> {
+    let winsize = libc::winsize {
+        ws_row: window_size.rows as u16,
+        ws_col: window_size.cols as u16,
+        ws_xpixel: window_size.xpixel as u16,
+        ws_ypixel: window_size.ypixel as u16,
+    };
+    // SAFETY: `file` is a valid, open fd for the lifetime of this call, and `winsize` is a valid
+    // in-pointer for the duration of the ioctl.
+    unsafe { raw::tiocswinsz(file.as_raw_fd(), &winsize) }.map_err(|e| {
+        Status::new_exception_str(
+            ExceptionCode::SERVICE_SPECIFIC,
+            Some(format!("TIOCSWINSZ ioctl() failed, errno={}", e as i32)),
+        )
+    })?;
+    Ok(())
+}
+
+/// Reads back the line settings and window size currently in effect on an already-open serial
+/// port file, the inverse of `apply_port_configuration`.
+fn read_port_configuration(
+    file: &std::fs::File,
+) -> std::result::Result<SerialPortConfiguration, Status> {
+    let attrs = termios::tcgetattr(file).map_err(|e| termios_error("tcgetattr", e))?;
+
+    let parity = if attrs.control_flags.contains(ControlFlags::PARENB | ControlFlags::PARODD) {
+        Parity::ODD
+    } else if attrs.control_flags.contains(ControlFlags::PARENB) {
+        Parity::EVEN
+    } else {
+        Parity::NONE
+    };
+    let flow_control = if attrs.control_flags.contains(ControlFlags::CRTSCTS) {
+        FlowControl::HARDWARE
+    } else if attrs.input_flags.contains(InputFlags::IXON | InputFlags::IXOFF) {
+        FlowControl::SOFTWARE
+    } else {
+        FlowControl::NONE
+    };
+
+    // SAFETY: `libc::winsize` is a plain-old-data struct of integers; an all-zero bit pattern is
+    // a valid value for it.
+    let mut winsize: libc::winsize = unsafe { std::mem::zeroed() };
+    // SAFETY: `file` is a valid, open fd for the lifetime of this call, and `winsize` is a valid,
+    // properly aligned out-pointer for the duration of the ioctl.
+    unsafe { raw::tiocgwinsz(file.as_raw_fd(), &mut winsize) }.map_err(|e| {
+        Status::new_exception_str(
+            ExceptionCode::SERVICE_SPECIFIC,
+            Some(format!("TIOCGWINSZ ioctl() failed, errno={}", e as i32)),
+        )
+    })?;
+
+    Ok(SerialPortConfiguration {
+        baudRate: baud_rate_to_value(termios::cfgetospeed(&attrs)),
+        dataBits: data_bits_from_flag(attrs.control_flags),
+        parity,
+        stopBits: if attrs.control_flags.contains(ControlFlags::CSTOPB) { 2 } else { 1 },
+        flowControl: flow_control,
+        windowSize: WindowSize {
+            rows: winsize.ws_row as i32,
+            cols: winsize.ws_col as i32,
+            xpixel: winsize.ws_xpixel as i32,
+            ypixel: winsize.ws_ypixel as i32,
+        },
+    })
+}
+
+/// Opens `port_name` and starts a broker task that fans reads out to every client registered via
+/// `BrokerHandle::add_client`, keeping the single real fd open for as long as the broker exists.
+/// `brokers` and `port_name` let the reader task deregister the broker from the registry itself
+/// once the port reader ends, so a dead fan-out with no reader never lingers for later callers.
+fn start_broker(
+    brokers: Arc<Mutex<HashMap<String, BrokerHandle>>>,
+    port_name: &str,
+) -> std::result::Result<BrokerHandle, Status> {
+    let path = Path::new("/dev").join(port_name);
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+        .open(&path)
+        .map_err(|e| {
+            Status::new_exception_str(
+                ExceptionCode::SERVICE_SPECIFIC,
+                Some(format!("open() failed, errno={}", e.raw_os_error().unwrap_or(0))),
+            )
+        })?;
+    let reader = file.try_clone().map_err(|e| {
+        Status::new_exception_str(ExceptionCode::SERVICE_SPECIFIC, Some(format!("{e}")))
+    })?;
+    let reader_fd = reader.as_raw_fd();
+    let port_writer = Arc::new(Mutex::new(file));
+    let clients: Arc<Mutex<HashMap<i32, UnixStream>>> = Arc::new(Mutex::new(HashMap::new()));
+    let (shutdown_read, shutdown_write) = shutdown_pipe().map_err(|e| {
+        Status::new_exception_str(ExceptionCode::SERVICE_SPECIFIC, Some(format!("{e}")))
+    })?;
+    let shutdown_read = Arc::new(shutdown_read);
+
+    let fanout_clients = clients.clone();
+    let port_name = port_name.to_string();
+    let task_shutdown_read = shutdown_read.clone();
+    let reader_task = tokio::task::spawn_blocking(move || {
+        let mut reader = reader;
+        let mut buf = [0u8; 4096];
+        let shut_down = loop {
+            if !wait_for_read_or_shutdown(reader_fd, task_shutdown_read.as_raw_fd()) {
+                break true;
+            }
+            match reader.read(&mut buf) {
+                Ok(0) => break false,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(_) => break false,
+                Ok(n) => {
+                    // Snapshot clones of the client fds and write to them without holding
+                    // `clients` locked, so a client that stops draining its socket only blocks
+                    // its own clone's write, not `add_client`'s insert or the next read's fan-out.
+                    let snapshot: Vec<(i32, UnixStream)> = fanout_clients
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .filter_map(|(&fd, client)| client.try_clone().ok().map(|clone| (fd, clone)))
+                        .collect();
+                    let dead: Vec<i32> = snapshot
+                        .into_iter()
+                        .filter_map(|(fd, mut client)| {
+                            client.write_all(&buf[..n]).is_err().then_some(fd)
+                        })
+                        .collect();
+                    if !dead.is_empty() {
+                        let mut clients = fanout_clients.lock().unwrap();
+                        for fd in dead {
+                            clients.remove(&fd);
+                        }
+                    }
+                }
+            }
+        };
+        if shut_down {
+            // The broker itself was torn down (`BrokerHandle` dropped, closing the shutdown
+            // pipe's write end): it has already been removed from `brokers`, so just let the
+            // port fd close with `reader`.
+            return;
+        }
+        // The port itself hit EOF or a read error: the fan-out is now permanently dead, so
+        // deregister the broker instead of leaving it in `brokers` for the next `requestShared`
+        // to find and hand new clients to.
+        brokers.lock().unwrap().remove(&port_name);
+    });
+
+    Ok(BrokerHandle { clients, port_writer, reader_task, shutdown_read, _shutdown_write: shutdown_write })
+}
+
+fn termios_error(call: &str, errno: nix::Error) -> Status {
+    Status::new_exception_str(
+        ExceptionCode::SERVICE_SPECIFIC,
+        Some(format!("{call}() failed, errno={}", errno as i32)),
+    )
+}
+
+fn baud_rate_from_value(baud_rate: i32) -> std::result::Result<BaudRate, Status> {
+    match baud_rate {
+        50 => Ok(BaudRate::B50),
+        75 => Ok(BaudRate::B75),
+        110 => Ok(BaudRate::B110),
+        134 => Ok(BaudRate::B134),
+        150 => Ok(BaudRate::B150),
+        200 => Ok(BaudRate::B200),
+        300 => Ok(BaudRate::B300),
+        600 => Ok(BaudRate::B600),
+        1200 => Ok(BaudRate::B1200),
+        1800 => Ok(BaudRate::B1800),
+        2400 => Ok(BaudRate::B2400),
+        4800 => Ok(BaudRate::B4800),
+        9600 => Ok(BaudRate::B9600),
+        19200 => Ok(BaudRate::B19200),
+        38400 => Ok(BaudRate::B38400),
+        57600 => Ok(BaudRate::B57600),
+        115200 => Ok(BaudRate::B115200),
+        230400 => Ok(BaudRate::B230400),
+        _ => Err(Status::new_exception_str(
+            ExceptionCode::ILLEGAL_ARGUMENT,
+            Some(format!("Unsupported baud rate {baud_rate}")),
+        )),
+    }
+}
+
+fn data_bits_flag(data_bits: i32) -> std::result::Result<ControlFlags, Status> {
+    match data_bits {
+        5 => Ok(ControlFlags::CS5),
+        6 => Ok(ControlFlags::CS6),
+        7 => Ok(ControlFlags::CS7),
+        8 => Ok(ControlFlags::CS8),
+        _ => Err(Status::new_exception_str(
+            ExceptionCode::ILLEGAL_ARGUMENT,
+            Some(format!("Unsupported data bits {data_bits}")),
+        )),
+    }
+}
+
+/// The inverse of `baud_rate_from_value`, used by `read_port_configuration`.
+fn baud_rate_to_value(baud_rate: BaudRate) -> i32 {
+    match baud_rate {
+        BaudRate::B50 => 50,
+        BaudRate::B75 => 75,
+        BaudRate::B110 => 110,
+        BaudRate::B134 => 134,
+        BaudRate::B150 => 150,
+        BaudRate::B200 => 200,
+        BaudRate::B300 => 300,
+        BaudRate::B600 => 600,
+        BaudRate::B1200 => 1200,
+        BaudRate::B1800 => 1800,
+        BaudRate::B2400 => 2400,
+        BaudRate::B4800 => 4800,
+        BaudRate::B9600 => 9600,
+        BaudRate::B19200 => 19200,
+        BaudRate::B38400 => 38400,
+        BaudRate::B57600 => 57600,
+        BaudRate::B115200 => 115200,
+        BaudRate::B230400 => 230400,
+        _ => 0,
+    }
+}
+
+/// The inverse of `data_bits_flag`, used by `read_port_configuration`.
+fn data_bits_from_flag(control_flags: ControlFlags) -> i32 {
+    match control_flags.intersection(ControlFlags::CSIZE) {
+        ControlFlags::CS5 => 5,
+        ControlFlags::CS6 => 6,
+        ControlFlags::CS7 => 7,
+        _ => 8,
+    }
 }
 
 impl DeviceEventCallback for SerialManager {
     fn on_device_added(&mut self, info: SerialPortInfo) {
         self.serial_ports.lock().unwrap().insert(info.name.clone(), info.clone());
+
+        let pending = PersistentIdentity::for_port(&info)
+            .and_then(|identity| self.pending_persistent.lock().unwrap().remove(&identity));
+        if let Some(pending) = pending {
+            self.persistent_ports.lock().unwrap().insert(info.name.clone());
+            if let Some(config) = &pending.config {
+                if let Ok(file) = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+                    .open(Path::new("/dev").join(&info.name))
+                {
+                    if let Err(e) = apply_port_configuration(&file, config) {
+                        log::warn!("Failed to reapply config to reattached '{}': {e:?}", info.name);
+                    }
+                }
+                self.stored_configs.lock().unwrap().insert(info.name.clone(), config.clone());
+            }
+            for listener_entry in self.listeners.lock().unwrap().values() {
+                if !matches_filter(&listener_entry.filter, &info) {
+                    continue;
+                }
+                listener_entry.listener.onSerialPortReattached(&info).unwrap_or_else(|error| {
+                    log::warn!("Error notifying listener: {error:?}");
+                });
+            }
+            return;
+        }
+
         for listener_entry in self.listeners.lock().unwrap().values() {
+            if !matches_filter(&listener_entry.filter, &info) {
+                continue;
+            }
             listener_entry.listener.onSerialPortConnected(&info).unwrap_or_else(|error| {
                 log::warn!("Error notifying listener: {error:?}");
             });
@@ -211,10 +1086,34 @@ impl DeviceEventCallback for SerialManager {
     }
 
     fn on_device_removed(&mut self, name: &str) {
+        self.forwarders.lock().unwrap().remove(name);
+        self.brokers.lock().unwrap().remove(name);
         let Some(info) = self.serial_ports.lock().unwrap().remove(name) else {
             return;
         };
+
+        if self.persistent_ports.lock().unwrap().remove(name) {
+            if let Some(identity) = PersistentIdentity::for_port(&info) {
+                let config = self.stored_configs.lock().unwrap().get(name).cloned();
+                self.pending_persistent
+                    .lock()
+                    .unwrap()
+                    .insert(identity, PendingPersistentPort { name: name.to_string(), config });
+                // A persistent port intentionally does not fire `onSerialPortDisconnected`: clients
+                // only learn about the gap if the device fails to reappear and they time out on
+                // their own, matching the "brief re-enumeration shouldn't force rediscovery" intent.
+                return;
+            }
+            // No stable identity to match a reappearing device against (e.g. a platform/UART
+            // node with no vendor/product/serial), so a reattach could never be recognized as
+            // such anyway: fall through and report a normal disconnect instead of leaking a
+            // pending entry that will never be claimed.
+        }
+
         for listener_entry in self.listeners.lock().unwrap().values() {
+            if !matches_filter(&listener_entry.filter, &info) {
+                continue;
+            }
             listener_entry.listener.onSerialPortDisconnected(&info).unwrap_or_else(|error| {
                 log::warn!("Error notifying listener: {error:?}");
             });
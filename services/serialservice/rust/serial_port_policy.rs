@@ -0,0 +1,108 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use android_hardware_serialservice::aidl::android::hardware::serialservice::SerialPortInfo::SerialPortInfo;
+
+/// Gates which serial ports are visible to clients and which may be opened, analogous to
+/// Chromium's USB device service `PermissionProvider`. Consulted by `DeviceEventsHandler` before
+/// a port is reported through discovery, and again by `SerialManager` on every open.
+#[mockall::automock]
+pub trait SerialPortPolicy {
+    /// Returns whether `info` should be visible to clients at all: reported through
+    /// `getSerialPorts`/listener callbacks and openable via `requestOpen`/`requestShared`.
+    fn is_visible(&self, info: &SerialPortInfo) -> bool;
+}
+
+/// An allowed `(vendorId, productId)` pair, or an allowed `subsystem` name when either ID is
+/// left as `-1` to match any value for that field.
+#[derive(Clone)]
+pub struct AllowedDevice {
+    pub vendor_id: i32,
+    pub product_id: i32,
+    pub subsystem: String,
+}
+
+/// Default `SerialPortPolicy` backed by a static allowlist of `(vendorId, productId)` pairs and
+/// `subsystem` names, given once when the policy is constructed at service start. The list is
+/// passed in by the caller; this type does not read it from a configuration file.
+pub struct AllowlistSerialPortPolicy {
+    allowed: Vec<AllowedDevice>,
+}
+
+impl AllowlistSerialPortPolicy {
+    pub fn new(allowed: Vec<AllowedDevice>) -> Self {
+        Self { allowed }
+    }
+}
+
+impl SerialPortPolicy for AllowlistSerialPortPolicy {
+    fn is_visible(&self, info: &SerialPortInfo) -> bool {
+        self.allowed.iter().any(|device| {
+            (device.vendor_id == -1 || device.vendor_id == info.vendorId)
+                && (device.product_id == -1 || device.product_id == info.productId)
+                && (device.subsystem.is_empty() || device.subsystem == info.subsystem)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port_info(vendor_id: i32, product_id: i32, subsystem: &str) -> SerialPortInfo {
+        SerialPortInfo {
+            name: "ttyACM0".to_string(),
+            subsystem: subsystem.to_string(),
+            driverType: "serial".to_string(),
+            vendorId: vendor_id,
+            productId: product_id,
+            serialNumber: String::new(),
+            manufacturer: String::new(),
+            product: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_exact_match_is_visible() {
+        let policy = AllowlistSerialPortPolicy::new(vec![AllowedDevice {
+            vendor_id: 0x0403,
+            product_id: 0x6001,
+            subsystem: "usb".to_string(),
+        }]);
+        assert!(policy.is_visible(&port_info(0x0403, 0x6001, "usb")));
+    }
+
+    #[test]
+    fn test_unlisted_device_is_not_visible() {
+        let policy = AllowlistSerialPortPolicy::new(vec![AllowedDevice {
+            vendor_id: 0x0403,
+            product_id: 0x6001,
+            subsystem: "usb".to_string(),
+        }]);
+        assert!(!policy.is_visible(&port_info(0x1A86, 0x7523, "usb")));
+    }
+
+    #[test]
+    fn test_subsystem_only_entry_matches_any_ids() {
+        let policy = AllowlistSerialPortPolicy::new(vec![AllowedDevice {
+            vendor_id: -1,
+            product_id: -1,
+            subsystem: "serial-base".to_string(),
+        }]);
+        assert!(policy.is_visible(&port_info(-1, -1, "serial-base")));
+        assert!(!policy.is_visible(&port_info(-1, -1, "usb")));
+    }
+}
@@ -23,9 +23,22 @@ use android_hardware_serialservice::binder;
 use anyhow::{bail, Result};
 use binder::BinderFeatures;
 use binder_tokio::TokioRuntime;
+use std::sync::Arc;
 use tokio::runtime::Handle;
 
 use serialservice::serial_manager::SerialManager;
+use serialservice::serial_port_policy::{AllowedDevice, AllowlistSerialPortPolicy};
+
+/// The default allowlist of devices visible through the wired-serial API. OEMs wishing to vet a
+/// different set of adapters should replace this list rather than patch the policy logic itself.
+fn default_allowed_devices() -> Vec<AllowedDevice> {
+    vec![
+        AllowedDevice { vendor_id: 0x0403, product_id: -1, subsystem: String::new() }, // FTDI
+        AllowedDevice { vendor_id: 0x10C4, product_id: -1, subsystem: String::new() }, // Silicon Labs
+        AllowedDevice { vendor_id: 0x1A86, product_id: -1, subsystem: String::new() }, // WCH
+        AllowedDevice { vendor_id: -1, product_id: -1, subsystem: "serial-base".to_string() },
+    ]
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -46,10 +59,11 @@ async fn main() -> Result<()> {
             .with_max_level(log::LevelFilter::Debug),
     );
 
+    let policy = Arc::new(AllowlistSerialPortPolicy::new(default_allowed_devices()));
     binder::add_service(
         "native_serial",
         BnSerialManager::new_async_binder(
-            SerialManager::new().await,
+            SerialManager::new(policy).await,
             TokioRuntime(Handle::current()),
             BinderFeatures::default(),
         )
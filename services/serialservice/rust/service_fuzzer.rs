@@ -21,14 +21,17 @@ use binder::BinderFeatures;
 use binder_random_parcel_rs::fuzz_service;
 use binder_tokio::TokioRuntime;
 use libfuzzer_sys::fuzz_target;
+use std::sync::Arc;
 use tokio::runtime::Builder;
 
 use serialservice::serial_manager::SerialManager;
+use serialservice::serial_port_policy::AllowlistSerialPortPolicy;
 
 fuzz_target!(|data: &[u8]| {
     let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    let policy = Arc::new(AllowlistSerialPortPolicy::new(Vec::new()));
     let service = BnSerialManager::new_async_binder(
-        runtime.block_on(async move { SerialManager::new().await }),
+        runtime.block_on(async move { SerialManager::new(policy).await }),
         TokioRuntime(runtime),
         BinderFeatures::default(),
     );
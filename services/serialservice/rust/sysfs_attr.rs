@@ -0,0 +1,36 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Small sysfs attribute readers shared by code that walks a `ueventd::device::Device` ancestry
+//! looking for USB/PCI identity strings, namely `device_events_handler` and `serial_device_info`.
+
+use anyhow::Result;
+use ueventd::device::FilesystemAttributeMap;
+
+/// Reads a hex sysfs attribute, accepting both the USB convention (`"0694"`) and the PCI
+/// convention (`"0x0694"`).
+pub(crate) fn read_hex_attr(attrs: &FilesystemAttributeMap, name: &str) -> Result<i32> {
+    let attr_value = attrs.get(name)?;
+    let hex_value = attr_value.trim();
+    let hex_value = hex_value.strip_prefix("0x").unwrap_or(hex_value);
+    Ok(i32::from_str_radix(hex_value, 16)?)
+}
+
+/// Reads a USB descriptor string sysfs attribute, trimming the trailing newline. Missing
+/// attributes (e.g. a device with no iSerialNumber descriptor) are treated as empty.
+pub(crate) fn read_string_attr(attrs: &FilesystemAttributeMap, name: &str) -> String {
+    attrs.get(name).map(|value| value.trim_end().to_string()).unwrap_or_default()
+}
@@ -0,0 +1,78 @@
+/*
+ * Copyright (C) 2025 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![cfg(test)]
+
+//! `MockSysfs` fixtures shared by `device_events_handler` and `serial_device_info`'s test
+//! modules, so both can exercise the same USB device ancestry without copy-pasting it.
+
+use std::collections::HashMap;
+use ueventd::device::Device;
+use ueventd::mock_sysfs::{MockSysfs, SysfsFile};
+
+/// Builds a USB-attached serial device (`ttyACM0`), nested two levels under a USB hub
+/// (`.../usb3/3-8/3-8:1.1/tty/ttyACM0`), with `idVendor`/`idProduct`/`serial`/`manufacturer`/
+/// `product` sysfs attributes on the outer ancestor (`3-8`).
+///
+/// When `with_own_device_subsystem` is set, `tty/ttyACM0` also gets its own nested `device`
+/// directory whose `subsystem` symlink resolves to `usb`, for tests that read `Device::device()`
+/// directly (as `device_events_handler` does to fill `SerialPortInfo::subsystem`).
+pub(crate) fn create_usb_device_in_mock_sysfs(
+    with_own_device_subsystem: bool,
+) -> (Device, MockSysfs) {
+    let mut ttyacm0 = HashMap::from([
+        ("subsystem", SysfsFile::Symlink("../../../../../../../../class/tty")),
+        ("uevent", SysfsFile::RegularFile("")),
+    ]);
+    if with_own_device_subsystem {
+        ttyacm0.insert(
+            "device",
+            SysfsFile::Dir(HashMap::from([
+                ("subsystem", SysfsFile::Symlink("../../../../../../../../../bus/usb")),
+                ("uevent", SysfsFile::RegularFile("")),
+            ])),
+        );
+    }
+    let sysfs = SysfsFile::Dir(HashMap::from([
+        (
+            "devices/pci0000:00/0000:00:14.0/usb3/3-8",
+            SysfsFile::Dir(HashMap::from([
+                (
+                    "3-8:1.1",
+                    SysfsFile::Dir(HashMap::from([
+                        ("tty/ttyACM0", SysfsFile::Dir(ttyacm0)),
+                        ("subsystem", SysfsFile::Symlink("../../../../../../bus/usb")),
+                        ("uevent", SysfsFile::RegularFile("")),
+                    ])),
+                ),
+                ("subsystem", SysfsFile::Symlink("../../../../../bus/usb")),
+                ("idVendor", SysfsFile::RegularFile("0694\n")),
+                ("idProduct", SysfsFile::RegularFile("0009\n")),
+                ("serial", SysfsFile::RegularFile("ABC123\n")),
+                ("manufacturer", SysfsFile::RegularFile("Acme Corp\n")),
+                ("product", SysfsFile::RegularFile("Serial Adapter\n")),
+                ("uevent", SysfsFile::RegularFile("")),
+            ])),
+        ),
+        ("bus/usb", SysfsFile::Dir(HashMap::new())),
+        ("class/tty", SysfsFile::Dir(HashMap::new())),
+    ]));
+    let sysfs_dir = MockSysfs::new(sysfs).expect("Could not create mock sysfs");
+    let sysfs_path =
+        sysfs_dir.path().join("devices/pci0000:00/0000:00:14.0/usb3/3-8/3-8:1.1/tty/ttyACM0");
+    let device = Device::with_root_and_syspath(sysfs_dir.path(), &sysfs_path).unwrap();
+    (device, sysfs_dir)
+}